@@ -310,10 +310,13 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { TorClient, TorClientBuilder, ConfigCircuitTiming, ConfigDirectoryTolerance, ConfigDownloadSchedule, ConfigNetParams, ConfigPathRules, ConfigPreemptiveCircuits, ConfigStorage, ConfigStreamTimeouts, PaddingLevel, TorClientConfig, OnionServiceConfig, OnionV3, RendRequest, OnionService, StateOnionService, StreamRequest, StreamsRequest, TorStream, StreamPrefs } = nativeBinding
+const { CancelToken, TorClient, TorClientBuilder, ConfigAddressFilter, ConfigChannel, ConfigCircuitTiming, ConfigDirectoryTolerance, ConfigDownloadSchedule, ConfigNetParams, ConfigPathRules, ConfigPreemptiveCircuits, ConfigStorage, ConfigStreamTimeouts, PaddingLevel, TorClientConfig, OnionServiceConfig, OnionV3, VanitySearch, RendRequest, RendRequestStream, OnionService, StateOnionService, StreamRequest, StreamsRequest, HttpResponse, Socks5ProxyListener, Socks5Server, TorStream, StreamPrefs } = nativeBinding
 
+module.exports.CancelToken = CancelToken
 module.exports.TorClient = TorClient
 module.exports.TorClientBuilder = TorClientBuilder
+module.exports.ConfigAddressFilter = ConfigAddressFilter
+module.exports.ConfigChannel = ConfigChannel
 module.exports.ConfigCircuitTiming = ConfigCircuitTiming
 module.exports.ConfigDirectoryTolerance = ConfigDirectoryTolerance
 module.exports.ConfigDownloadSchedule = ConfigDownloadSchedule
@@ -326,10 +329,15 @@ module.exports.PaddingLevel = PaddingLevel
 module.exports.TorClientConfig = TorClientConfig
 module.exports.OnionServiceConfig = OnionServiceConfig
 module.exports.OnionV3 = OnionV3
+module.exports.VanitySearch = VanitySearch
 module.exports.RendRequest = RendRequest
+module.exports.RendRequestStream = RendRequestStream
 module.exports.OnionService = OnionService
 module.exports.StateOnionService = StateOnionService
 module.exports.StreamRequest = StreamRequest
 module.exports.StreamsRequest = StreamsRequest
+module.exports.HttpResponse = HttpResponse
+module.exports.Socks5ProxyListener = Socks5ProxyListener
+module.exports.Socks5Server = Socks5Server
 module.exports.TorStream = TorStream
 module.exports.StreamPrefs = StreamPrefs
@@ -0,0 +1,40 @@
+use tokio_util::sync::CancellationToken;
+
+#[napi(js_name = "CancelToken")]
+#[derive(Default)]
+pub struct NativeCancelToken {
+  token: CancellationToken,
+}
+
+#[napi]
+impl NativeCancelToken {
+  #[napi(constructor)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  #[napi(factory)]
+  pub fn create() -> Self {
+    Self::new()
+  }
+
+  /**
+   * Cancels every operation observing this token.
+   */
+  #[napi]
+  pub fn cancel(&self) {
+    self.token.cancel();
+  }
+
+  /**
+   * Whether `cancel()` has been called.
+   */
+  #[napi(getter)]
+  pub fn is_cancelled(&self) -> bool {
+    self.token.is_cancelled()
+  }
+
+  pub fn get(&self) -> CancellationToken {
+    self.token.clone()
+  }
+}
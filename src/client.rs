@@ -1,24 +1,285 @@
 use crate::client_builder::NativeTorClientBuilder;
-use crate::hs_config::NativeOnionServiceConfig;
-use crate::hs_service::NativeOnionService;
+use crate::events::{ClientEvents, EventPayload, BOOTSTRAP_COMPLETE, STREAM_CONNECTED};
+use crate::hs_config::{NativeOnionServiceConfig, RequestLimits};
+use crate::hs_onion_v3::NativeOnionV3;
+use crate::hs_service::{NativeOnionService, ServiceCounters};
+use crate::logging;
 use crate::stream::NativeTorStream;
-use crate::stream_prefs::NativeStreamPrefs;
+use crate::stream_prefs::{IsolationKey, NativeStreamPrefs};
 use crate::utils;
-use arti_client::TorClient;
-use napi::JsBuffer;
-use tor_hscrypto::pk::HsIdKeypair;
+use arti_client::config::onion_service::OnionServiceConfigBuilder;
+use arti_client::{CountryCode, DataStream, ErrorKind, HasKind, StreamPrefs, TorClient};
+use futures_core::Stream;
+use napi::bindgen_prelude::Buffer;
+use napi::threadsafe_function::ThreadsafeFunctionCallMode;
+use napi::{Env, JsBuffer, JsFunction, JsObject};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+const RETRY_BACKOFF_BASE_MS: u64 = 500;
+const RETRY_BACKOFF_MAX_MS: u64 = 30_000;
+/// Hostname resolved by `waitForExitCountry()` to build an exit circuit.
+const PROBE_HOSTNAME: &str = "www.torproject.org";
+/// Keeps `isolatedGroup()` keys apart from caller-provided isolation keys.
+const ISOLATION_GROUP_PREFIX: &[u8] = b"pynk-isolation-group:";
+use tor_circmgr::timeouts::Action;
+use tor_hscrypto::pk::{HsClientDescEncSecretKey, HsId, HsIdKeypair};
+use tor_hsservice::RendRequest;
+use tor_keymgr::KeystoreSelector;
+use tor_llcrypto::pk::curve25519;
 use tor_llcrypto::pk::ed25519::{ExpandedKeypair, Keypair};
+use tor_netdir::Timeliness;
 use tor_rtcompat::PreferredRuntime;
 
+/**
+ * Per-connection circuit parameters used by `TorClient.connectWithCircuitParams()`.
+ */
+#[napi(object, js_name = "CircuitParams")]
+pub struct NativeCircuitParams {
+  /// Two-letter ISO 3166-1 alpha-2 country code the exit relay must be located in.
+  pub exit_country: Option<String>,
+  /// Number of hops in the circuit. Arti always builds 3-hop exit circuits, so only `3` is accepted.
+  pub num_hops: Option<u8>,
+  /// Require relays with the `Stable` flag. Not configurable per connection in arti.
+  pub stable: Option<bool>,
+  /// Require relays with the `Fast` flag. Not configurable per connection in arti.
+  pub fast: Option<bool>,
+  /// Connections using the same key may share circuits; different keys never do.
+  pub isolation_key: Option<Buffer>,
+}
+
+/**
+ * Snapshot of the client's network health returned by `TorClient.getNetworkStatus()`.
+ */
+#[napi(object, object_from_js = false, js_name = "NetworkStatus")]
+pub struct NativeNetworkStatus {
+  /// Whether the client is ready to carry traffic.
+  pub is_bootstrapped: bool,
+  /// Bootstrap progress, from 0 to 100.
+  pub bootstrap_progress_percent: u8,
+  /// Time since the current consensus became valid, in seconds; `0` if there is none yet.
+  pub last_consensus_age_seconds: u32,
+  /// Number of usable relays listed in the current consensus; `0` if there is none yet.
+  pub usable_relays: u32,
+  /// Whether the client is in dormant mode. Clients created by this package are never dormant.
+  pub dormant: bool,
+}
+
+impl NativeCircuitParams {
+  fn to_stream_prefs(&self) -> napi::Result<StreamPrefs> {
+    if self.num_hops.is_some_and(|hops| hops != 3) {
+      return Err(napi::Error::from_reason(
+        "Only 3-hop circuits are supported for `numHops`",
+      ));
+    }
+
+    if self.stable.is_some() || self.fast.is_some() {
+      return Err(napi::Error::from_reason(
+        "Relay flag requirements (`stable`, `fast`) cannot be set per connection",
+      ));
+    }
+
+    let mut prefs = StreamPrefs::new();
+
+    if let Some(country_code) = &self.exit_country {
+      prefs.exit_country(utils::map_error(CountryCode::from_str(country_code))?);
+    }
+
+    if let Some(key) = &self.isolation_key {
+      prefs.set_isolation(IsolationKey::new(key.to_vec()));
+    }
+
+    Ok(prefs)
+  }
+}
+
+/// Whether a failed connection attempt may succeed if repeated, e.g. after a circuit failure or timeout.
+fn is_retryable(err: &arti_client::Error) -> bool {
+  matches!(
+    err.kind(),
+    ErrorKind::TorAccessFailed
+      | ErrorKind::TorNetworkTimeout
+      | ErrorKind::TorDirectoryError
+      | ErrorKind::CircuitCollapse
+      | ErrorKind::CircuitRefused
+      | ErrorKind::TransientFailure
+      | ErrorKind::ExitTimeout
+      | ErrorKind::RelayTooBusy
+      | ErrorKind::NoPath
+      | ErrorKind::NoExit
+      | ErrorKind::LocalNetworkError
+      | ErrorKind::RemoteNetworkTimeout
+      | ErrorKind::RemoteNetworkFailed
+      | ErrorKind::OnionServiceConnectionFailed
+      | ErrorKind::OnionServiceNotRunning
+  )
+}
+
+/// Bootstraps the client, failing if it takes longer than `timeout_ms`.
+pub async fn bootstrap_with_timeout(
+  client: &TorClient<PreferredRuntime>,
+  timeout_ms: u32,
+) -> napi::Result<()> {
+  let timeout = Duration::from_millis(timeout_ms as u64);
+
+  match tokio::time::timeout(timeout, client.bootstrap()).await {
+    Ok(result) => utils::map_error(result),
+    Err(_) => Err(napi::Error::from_reason(format!(
+      "Bootstrap did not complete within {timeout_ms}ms"
+    ))),
+  }
+}
+
+/// Connects like `TorClient::connect_with_prefs`, returning `None` if the connection takes longer than `timeout_ms`.
+async fn try_connect(
+  client: &TorClient<PreferredRuntime>,
+  address: &str,
+  prefs: &StreamPrefs,
+  timeout_ms: Option<u32>,
+) -> Option<arti_client::Result<DataStream>> {
+  match timeout_ms {
+    Some(timeout_ms) => {
+      let timeout = Duration::from_millis(timeout_ms as u64);
+      tokio::time::timeout(timeout, client.connect_with_prefs(address, prefs))
+        .await
+        .ok()
+    }
+    None => Some(client.connect_with_prefs(address, prefs).await),
+  }
+}
+
+fn connect_timeout_error(address: &str, timeout_ms: Option<u32>) -> napi::Error {
+  napi::Error::from_reason(format!(
+    "Connection to {address} did not complete within {}ms",
+    timeout_ms.unwrap_or_default()
+  ))
+}
+
+/// Connects like `TorClient::connect_with_prefs`, failing if the connection takes longer than `timeout_ms`.
+async fn connect_with_timeout(
+  client: &TorClient<PreferredRuntime>,
+  address: &str,
+  prefs: &StreamPrefs,
+  timeout_ms: Option<u32>,
+) -> napi::Result<DataStream> {
+  match try_connect(client, address, prefs, timeout_ms).await {
+    Some(stream) => utils::map_error(stream),
+    None => Err(connect_timeout_error(address, timeout_ms)),
+  }
+}
+
+/// Parses an Onion v3 address, with or without the `.onion` suffix, into the service identity.
+fn parse_hsid(address: &str) -> napi::Result<HsId> {
+  let address = address.to_ascii_lowercase();
+  let address = if address.ends_with(".onion") {
+    address
+  } else {
+    format!("{address}.onion")
+  };
+
+  HsId::from_str(&address)
+    .map_err(|_| napi::Error::from_reason(format!("Invalid Onion v3 address: {address}")))
+}
+
+/// Bytes carried by the streams of a client and of the handles derived from it.
+#[derive(Default)]
+pub struct TransferCounters {
+  bytes_in: AtomicU64,
+  bytes_out: AtomicU64,
+}
+
+impl TransferCounters {
+  pub fn bytes_received(&self, bytes: usize) {
+    self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+  }
+
+  pub fn bytes_sent(&self, bytes: usize) {
+    self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+  }
+}
+
+/// Connects like `TorClient.connect()` on behalf of a handle, for connections made outside of it such as the SOCKS5 proxy.
+/// Uses the handle's current preferences and default timeout, and reports to its counters and listeners.
+#[derive(Clone)]
+pub struct ClientConnector {
+  client: TorClient<PreferredRuntime>,
+  events: ClientEvents,
+  prefs: Arc<RwLock<StreamPrefs>>,
+  connect_timeout: Option<u32>,
+  transfer: Arc<TransferCounters>,
+}
+
+impl ClientConnector {
+  pub async fn connect(&self, address: &str) -> napi::Result<DataStream> {
+    let prefs = self.prefs.read().unwrap().clone();
+    let stream = connect_with_timeout(&self.client, address, &prefs, self.connect_timeout).await?;
+    self
+      .events
+      .emit(STREAM_CONNECTED, EventPayload::Text(address.to_string()));
+    Ok(stream)
+  }
+
+  pub fn transfer(&self) -> &TransferCounters {
+    &self.transfer
+  }
+}
+
 #[napi(js_name = "TorClient")]
 pub struct NativeTorClient {
   client: TorClient<PreferredRuntime>,
+  events: ClientEvents,
+  // Behind a lock so preferences can be replaced through a shared reference, see `reconfigure_stream_prefs`.
+  // Shared with the `ClientConnector`s of this handle.
+  prefs: Arc<RwLock<StreamPrefs>>,
+  connect_timeout: Option<u32>,
+  transfer: Arc<TransferCounters>,
+  fallback_directories: u32,
 }
 
 #[napi]
 impl NativeTorClient {
   pub fn from_client(client: TorClient<PreferredRuntime>) -> Self {
-    Self { client }
+    Self {
+      client,
+      events: ClientEvents::default(),
+      prefs: Arc::default(),
+      connect_timeout: None,
+      transfer: Arc::default(),
+      fallback_directories: NativeTorClientBuilder::default().fallback_directory_count(),
+    }
+  }
+
+  fn from_builder(client: TorClient<PreferredRuntime>, builder: &NativeTorClientBuilder) -> Self {
+    let events = ClientEvents::default();
+    events.drive(
+      client.bootstrap_events(),
+      client.bootstrap_status().ready_for_traffic(),
+    );
+
+    Self {
+      client,
+      events,
+      prefs: Arc::default(),
+      connect_timeout: builder.default_connect_timeout(),
+      transfer: Arc::default(),
+      fallback_directories: builder.fallback_directory_count(),
+    }
+  }
+
+  fn stream_prefs(&self) -> StreamPrefs {
+    self.prefs.read().unwrap().clone()
+  }
+
+  pub fn connector(&self) -> ClientConnector {
+    ClientConnector {
+      client: self.client.clone(),
+      events: self.events.clone(),
+      prefs: self.prefs.clone(),
+      connect_timeout: self.connect_timeout,
+      transfer: self.transfer.clone(),
+    }
   }
 
   /**
@@ -33,14 +294,103 @@ impl NativeTorClient {
    */
   #[napi(factory)]
   pub async fn create(builder: Option<&NativeTorClientBuilder>) -> napi::Result<Self> {
-    let client = if let Some(builder) = builder {
-      builder.build().await
-    } else {
-      NativeTorClientBuilder::default().build().await
+    let default_builder;
+    let builder = match builder {
+      Some(builder) => builder,
+      None => {
+        default_builder = NativeTorClientBuilder::default();
+        &default_builder
+      }
     };
 
-    let client = utils::map_error(client)?;
-    Ok(Self { client })
+    let client = builder.build().await?;
+    Ok(Self::from_builder(client, builder))
+  }
+
+  /**
+   * Creates a new instance of the Tor client without bootstrapping it, so that listeners can be registered with `on()`
+   * before any progress is made. Call `bootstrap()` to bootstrap the client; connections also bootstrap it on demand.
+   * The builder's `bootstrapTimeout()` does not apply; use `waitBootstrappedWithTimeout()` to bound the bootstrap.
+   *
+   * @param builder - Optional reference to a `NativeTorClientBuilder` to customize the client configuration.
+   */
+  #[napi(factory)]
+  pub async fn create_unbootstrapped(
+    builder: Option<&NativeTorClientBuilder>,
+  ) -> napi::Result<Self> {
+    let default_builder;
+    let builder = match builder {
+      Some(builder) => builder,
+      None => {
+        default_builder = NativeTorClientBuilder::default();
+        &default_builder
+      }
+    };
+
+    let client = builder.build_unbootstrapped().await?;
+    Ok(Self::from_builder(client, builder))
+  }
+
+  /**
+   * Bootstraps the client: downloads enough directory information to build circuits.
+   * Resolves immediately if the client is already bootstrapped.
+   */
+  #[napi]
+  pub async fn bootstrap(&self) -> napi::Result<()> {
+    utils::map_error(self.client.bootstrap().await)
+  }
+
+  /**
+   * Waits until the client has bootstrapped, i.e. it has enough directory information to build circuits.
+   * Resolves immediately if it already has.
+   *
+   * @param timeoutMs - Maximum time to wait in milliseconds.
+   * @throws `"Bootstrap did not complete within Nms"` if the timeout elapses first.
+   */
+  #[napi]
+  pub async fn wait_bootstrapped_with_timeout(&self, timeout_ms: u32) -> napi::Result<()> {
+    bootstrap_with_timeout(&self.client, timeout_ms).await
+  }
+
+  /**
+   * Registers a listener for a client event:
+   * - `"bootstrap:progress"`: bootstrap progress changed; receives the fraction completed, from 0 to 1.
+   * - `"bootstrap:complete"`: the client became ready for traffic. Called immediately if it already is.
+   * - `"stream:connected"`: a connection made with this client was established; receives the target address.
+   * - `"network:status"`: the bootstrap status changed, including when the network becomes blocked; receives a description.
+   *
+   * Listeners are shared with the handles returned by `isolated()` and `newCircuit()`, and do not keep the process alive.
+   * `TorClient.create()` only resolves once bootstrapping is complete; to observe `"bootstrap:progress"`, create the
+   * client with `TorClient.createUnbootstrapped()`, register the listeners and then call `bootstrap()`.
+   */
+  #[napi(ts_args_type = "event: string, callback: (...args: any[]) => any")]
+  pub fn on(&self, env: Env, event: String, callback: JsFunction) -> napi::Result<()> {
+    let immediate =
+      event == BOOTSTRAP_COMPLETE && self.client.bootstrap_status().ready_for_traffic();
+    let listener = self.events.on(&env, event, callback)?;
+
+    if immediate {
+      listener.call(EventPayload::None, ThreadsafeFunctionCallMode::NonBlocking);
+    }
+    Ok(())
+  }
+
+  /**
+   * Removes every listener registered for the given event.
+   */
+  #[napi]
+  pub fn off(&self, event: String) {
+    self.events.off(&event);
+  }
+
+  /**
+   * Registers a listener for Arti's log records, replacing any previous one.
+   * Logging is process-wide: the listener receives records from every client, and is the same one
+   * set by `TorClientBuilder.onLog()`. Use `TorClientBuilder.setLogLevel()` to change the level, `"info"` by default.
+   */
+  #[napi(ts_args_type = "callback: (record: LogRecord) => void")]
+  pub fn on_log(&self, env: Env, callback: JsFunction) -> napi::Result<()> {
+    logging::set_listener(&env, callback)
   }
 
   /**
@@ -52,7 +402,106 @@ impl NativeTorClient {
    */
   #[napi]
   pub fn isolated(&self) -> Self {
-    Self::from_client(self.client.isolated_client())
+    Self {
+      client: self.client.isolated_client(),
+      events: self.events.clone(),
+      prefs: Arc::new(RwLock::new(self.stream_prefs())),
+      connect_timeout: self.connect_timeout,
+      transfer: self.transfer.clone(),
+      fallback_directories: self.fallback_directories,
+    }
+  }
+
+  /**
+   * Return a TorClient handle whose connections are tagged with the isolation group `groupId`.
+   * Handles created from the same client with the same `groupId` may share circuits with each other,
+   * but never with other groups or with untagged connections, e.g. to give each user session its own circuits.
+   * The handle keeps the preferences of this client, and shares its listeners.
+   *
+   * @param groupId - Numeric identifier of the isolation group.
+   */
+  #[napi]
+  pub fn isolated_group(&self, group_id: u32) -> napi::Result<Self> {
+    let mut prefs = self.stream_prefs();
+    prefs.set_isolation(IsolationKey::new(
+      [ISOLATION_GROUP_PREFIX, &group_id.to_be_bytes()].concat(),
+    ));
+
+    Ok(Self {
+      client: self.client.clone(),
+      events: self.events.clone(),
+      prefs: Arc::new(RwLock::new(prefs)),
+      connect_timeout: self.connect_timeout,
+      transfer: self.transfer.clone(),
+      fallback_directories: self.fallback_directories,
+    })
+  }
+
+  /**
+   * Return a short-lived TorClient handle whose next connection is guaranteed to use a fresh circuit.
+   * Use a new handle for every connection that must not share a circuit with anything made before it.
+   * Subsequent connections made with the same handle (or its clones) may still reuse that circuit.
+   * Unlike `StreamPrefs.isolateEveryStream()`, this does not affect the streams of any other handle.
+   */
+  #[napi]
+  pub fn new_circuit(&self) -> napi::Result<Self> {
+    Ok(self.isolated())
+  }
+
+  /**
+   * Waits until an exit circuit through a relay in the given country can be built, then returns an isolated
+   * TorClient handle (see `isolated()`) whose connections exit from that country.
+   * The circuit is built by the returned handle itself, by resolving `www.torproject.org` through it, so its first
+   * connections can reuse that circuit. Bootstraps the client first if needed. Failed attempts are retried every 500ms.
+   *
+   * @param countryCode - A two-letter ISO 3166-1 alpha-2 country code, e.g. `"DE"`.
+   * @param timeoutMs - Maximum time to wait in milliseconds. Waits indefinitely if omitted.
+   * @throws If the timeout elapses first, including the last circuit error if there was one.
+   */
+  #[napi]
+  pub async fn wait_for_exit_country(
+    &self,
+    country_code: String,
+    timeout_ms: Option<u32>,
+  ) -> napi::Result<NativeTorClient> {
+    let country = utils::map_error(CountryCode::from_str(&country_code))?;
+    let handle = self.isolated();
+    handle.prefs.write().unwrap().exit_country(country);
+    let prefs = handle.stream_prefs();
+    let mut last_error = None;
+
+    let wait = async {
+      utils::map_error(handle.client.bootstrap().await)?;
+
+      loop {
+        match handle
+          .client
+          .resolve_with_prefs(PROBE_HOSTNAME, &prefs)
+          .await
+        {
+          Ok(_) => return napi::Result::Ok(()),
+          Err(err) => last_error = Some(err.to_string()),
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+      }
+    };
+
+    match timeout_ms {
+      Some(timeout_ms) => {
+        let timeout = Duration::from_millis(timeout_ms as u64);
+        let result = tokio::time::timeout(timeout, wait).await;
+        result.map_err(|_| {
+          let reason = last_error.map_or(String::new(), |err| format!(": {err}"));
+          napi::Error::from_reason(format!(
+            "No circuit exiting in {country} could be built within {timeout_ms}ms{reason}"
+          ))
+        })??;
+      }
+      None => wait.await?,
+    }
+
+    Ok(handle)
   }
 
   /**
@@ -60,6 +509,7 @@ impl NativeTorClient {
    * Note that because Tor prefers to do DNS resolution on the remote side of the network, this function takes its address as a string:
    *
    *  @param address - The target address and port as a string, **important:** it must be in the format `url:port` (e.g. `"httpbin.org:80"`).
+   *  @param timeoutMs - Optional time limit in milliseconds, overriding `TorClientBuilder.connectTimeout()`.
    *
    * @example
    * ```ts
@@ -72,10 +522,367 @@ impl NativeTorClient {
    * ```
    */
   #[napi]
-  pub async fn connect(&self, address: String) -> napi::Result<NativeTorStream> {
-    let stream = self.client.connect(&address).await;
-    let stream = utils::map_error(stream)?;
-    Ok(NativeTorStream::from_stream(stream))
+  pub async fn connect(
+    &self,
+    address: String,
+    timeout_ms: Option<u32>,
+  ) -> napi::Result<NativeTorStream> {
+    let timeout_ms = timeout_ms.or(self.connect_timeout);
+    let prefs = self.stream_prefs();
+    let started = Instant::now();
+    let stream = connect_with_timeout(&self.client, &address, &prefs, timeout_ms).await?;
+    self
+      .events
+      .emit(STREAM_CONNECTED, EventPayload::Text(address));
+    Ok(NativeTorStream::from_client_stream(
+      stream,
+      self.transfer.clone(),
+      started,
+    ))
+  }
+
+  /**
+   * Launch an anonymized connection like `connect()`, wait for it to be established and send `data` on it.
+   * Equivalent to calling `connect()`, `waitForConnection()`, `write()` and `flush()`, closing the stream
+   * if any of them fails. Useful for request-response protocols such as HTTP.
+   *
+   * @param address - The target address and port as a string (e.g. `"httpbin.org:80"`).
+   * @param data - The bytes to send once connected.
+   */
+  #[napi]
+  pub async fn connect_and_send(
+    &self,
+    address: String,
+    data: Buffer,
+  ) -> napi::Result<NativeTorStream> {
+    let mut stream = self.connect(address, None).await?;
+
+    // The stream is not reachable from JavaScript yet, so nothing else can use it concurrently.
+    let sent = unsafe {
+      async {
+        stream.wait_for_connection().await?;
+        stream.write(data).await?;
+        stream.flush().await
+      }
+      .await
+    };
+
+    match sent {
+      Ok(()) => Ok(stream),
+      Err(err) => {
+        unsafe { stream.close() };
+        Err(err)
+      }
+    }
+  }
+
+  /**
+   * Launch `count` connections to the same address concurrently, like calling `connect()` `count` times.
+   * Returns one Promise per connection, so a failed connection does not affect the others.
+   * Useful to open many streams to a single onion service at once.
+   *
+   * @param address - The target address and port as a string (e.g. `"httpbin.org:80"`).
+   * @param count - Number of connections to launch.
+   */
+  #[napi(ts_return_type = "Array<Promise<TorStream>>")]
+  pub fn connect_bulk(&self, env: Env, address: String, count: u32) -> napi::Result<Vec<JsObject>> {
+    (0..count)
+      .map(|_| {
+        let client = self.client.clone();
+        let events = self.events.clone();
+        let transfer = self.transfer.clone();
+        let prefs = self.stream_prefs();
+        let timeout_ms = self.connect_timeout;
+        let address = address.clone();
+
+        env.spawn_future(async move {
+          let started = Instant::now();
+          let stream = connect_with_timeout(&client, &address, &prefs, timeout_ms).await?;
+          events.emit(STREAM_CONNECTED, EventPayload::Text(address));
+          Ok(NativeTorStream::from_client_stream(
+            stream, transfer, started,
+          ))
+        })
+      })
+      .collect()
+  }
+
+  /**
+   * Launch an anonymized connection to an onion service, like `connect()`, enabling onion connections for this call
+   * regardless of the client's preferences.
+   *
+   * Uses the default time limit set with `TorClientBuilder.connectTimeout()`, if any.
+   *
+   * @param address - The Onion v3 address and port as a string (e.g. `"duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion:80"`).
+   * @throws `InvalidOnionAddress` if the host is not a valid Onion v3 address or the port is missing,
+   * before any connection is attempted.
+   */
+  #[napi]
+  pub async fn connect_hidden_service(&self, address: String) -> napi::Result<NativeTorStream> {
+    let valid = address.rsplit_once(':').is_some_and(|(host, port)| {
+      host.to_ascii_lowercase().ends_with(".onion")
+        && NativeOnionV3::validate_address(host.to_string())
+        && port.parse::<u16>().is_ok()
+    });
+    if !valid {
+      return Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("InvalidOnionAddress: {address}"),
+      ));
+    }
+
+    let mut prefs = self.stream_prefs();
+    prefs.connect_to_onion_services(tor_config::BoolOrAuto::Explicit(true));
+
+    let started = Instant::now();
+    let stream = connect_with_timeout(&self.client, &address, &prefs, self.connect_timeout).await?;
+    self
+      .events
+      .emit(STREAM_CONNECTED, EventPayload::Text(address));
+    Ok(NativeTorStream::from_client_stream(
+      stream,
+      self.transfer.clone(),
+      started,
+    ))
+  }
+
+  /**
+   * Launch an anonymized connection like `connect()`, but return the stream immediately.
+   * The circuit is built in the background, and the stream is opened "optimistically" (see `StreamPrefs.optimistic()`)
+   * for this call only, without changing the client's preferences. Reads and writes wait for the circuit;
+   * connection errors, including exceeding `TorClientBuilder.connectTimeout()`, are reported by the first operation on the stream.
+   *
+   * @param address - The target address and port as a string (e.g. `"httpbin.org:80"`).
+   */
+  #[napi]
+  pub fn connect_optimistic(&self, address: String) -> napi::Result<NativeTorStream> {
+    let client = self.client.clone();
+    let events = self.events.clone();
+    let mut prefs = self.stream_prefs();
+    prefs.optimistic();
+    let timeout_ms = self.connect_timeout;
+
+    let pending = napi::bindgen_prelude::within_runtime_if_available(|| {
+      tokio::spawn(async move {
+        let stream = connect_with_timeout(&client, &address, &prefs, timeout_ms).await?;
+        events.emit(STREAM_CONNECTED, EventPayload::Text(address));
+        Ok(stream)
+      })
+    });
+
+    Ok(NativeTorStream::from_pending_stream(
+      pending,
+      self.transfer.clone(),
+    ))
+  }
+
+  /**
+   * Launch an anonymized connection like `connect()`, retrying transient failures.
+   * Circuit failures and timeouts, including the default time limit set with `TorClientBuilder.connectTimeout()`,
+   * are retried up to `maxRetries` times; other errors, such as an invalid address, are returned immediately.
+   * If every attempt fails, the last error is returned.
+   *
+   * @param address - The target address and port as a string (e.g. `"httpbin.org:80"`).
+   * @param maxRetries - Number of retries after the first attempt.
+   * @param delayMs - Delay between attempts in milliseconds; `0` uses exponential backoff starting at 500ms.
+   */
+  #[napi]
+  pub async fn connect_with_retry(
+    &self,
+    address: String,
+    max_retries: u32,
+    delay_ms: u32,
+  ) -> napi::Result<NativeTorStream> {
+    let prefs = self.stream_prefs();
+    let mut attempt = 0;
+
+    loop {
+      let started = Instant::now();
+      match try_connect(&self.client, &address, &prefs, self.connect_timeout).await {
+        Some(Ok(stream)) => {
+          self
+            .events
+            .emit(STREAM_CONNECTED, EventPayload::Text(address));
+          return Ok(NativeTorStream::from_client_stream(
+            stream,
+            self.transfer.clone(),
+            started,
+          ));
+        }
+        Some(Err(err)) if attempt == max_retries || !is_retryable(&err) => {
+          return utils::map_error(Err(err));
+        }
+        None if attempt == max_retries => {
+          return Err(connect_timeout_error(&address, self.connect_timeout));
+        }
+        _ => {
+          let delay = if delay_ms == 0 {
+            RETRY_BACKOFF_BASE_MS
+              .saturating_mul(1 << attempt.min(16))
+              .min(RETRY_BACKOFF_MAX_MS)
+          } else {
+            delay_ms as u64
+          };
+
+          tokio::time::sleep(Duration::from_millis(delay)).await;
+          attempt += 1;
+        }
+      }
+    }
+  }
+
+  /**
+   * Launch an anonymized connection like `connect()`, applying the given circuit parameters to this connection only.
+   * The parameters replace the client's default stream preferences for this call; the default time limit set with
+   * `TorClientBuilder.connectTimeout()` still applies.
+   *
+   * @param address - The target address and port as a string (e.g. `"httpbin.org:80"`).
+   * @param params - Exit country, isolation key and other circuit constraints.
+   */
+  #[napi]
+  pub async fn connect_with_circuit_params(
+    &self,
+    address: String,
+    params: NativeCircuitParams,
+  ) -> napi::Result<NativeTorStream> {
+    let prefs = params.to_stream_prefs()?;
+    let started = Instant::now();
+    let stream = connect_with_timeout(&self.client, &address, &prefs, self.connect_timeout).await?;
+    self
+      .events
+      .emit(STREAM_CONNECTED, EventPayload::Text(address));
+    Ok(NativeTorStream::from_client_stream(
+      stream,
+      self.transfer.clone(),
+      started,
+    ))
+  }
+
+  /**
+   * Resolves a hostname to IP addresses over the Tor network, using the given preferences
+   * (e.g. exit country) instead of the client's defaults for this lookup.
+   *
+   * @param hostname - The hostname to resolve, e.g. `"torproject.org"`.
+   * @param prefs - Stream preferences applied to the resolve request.
+   */
+  #[napi]
+  pub async fn resolve_with_prefs(
+    &self,
+    hostname: String,
+    prefs: &NativeStreamPrefs,
+  ) -> napi::Result<Vec<String>> {
+    let prefs = prefs.get();
+    let addrs = self.client.resolve_with_prefs(&hostname, &prefs).await;
+    let addrs = utils::map_error(addrs)?;
+    Ok(addrs.iter().map(|addr| addr.to_string()).collect())
+  }
+
+  /**
+   * Returns a snapshot of the client's bootstrap state and directory information.
+   * Works before bootstrapping completes, reporting no consensus until one has been downloaded.
+   */
+  #[napi]
+  pub fn get_network_status(&self) -> napi::Result<NativeNetworkStatus> {
+    let status = self.client.bootstrap_status();
+    let netdir = self.client.dirmgr().netdir(Timeliness::Unchecked).ok();
+
+    let last_consensus_age_seconds = netdir
+      .as_ref()
+      .and_then(|netdir| {
+        SystemTime::now()
+          .duration_since(netdir.lifetime().valid_after())
+          .ok()
+      })
+      .map_or(0, |age| age.as_secs().min(u32::MAX as u64) as u32);
+    let usable_relays = netdir
+      .as_ref()
+      .map_or(0, |netdir| netdir.relays().count() as u32);
+
+    Ok(NativeNetworkStatus {
+      is_bootstrapped: status.ready_for_traffic(),
+      bootstrap_progress_percent: (status.as_frac() * 100.0).round() as u8,
+      last_consensus_age_seconds,
+      usable_relays,
+      dormant: false,
+    })
+  }
+
+  /**
+   * The time currently allowed to build a 3-hop circuit, in milliseconds.
+   * Arti adapts it to the build times it observes, starting from a default until enough circuits have been built.
+   */
+  #[napi(getter)]
+  pub fn circuit_build_timeout_ms(&self) -> u32 {
+    let timeout = self
+      .client
+      .circmgr()
+      .estimate_timeout(&Action::BuildCircuit { length: 3 });
+    timeout.as_millis().min(u32::MAX as u128) as u32
+  }
+
+  /**
+   * Returns the number of fallback directory caches configured for this client.
+   * Fallbacks are used to fetch the first consensus; Arti does not report which of them are reachable.
+   */
+  #[napi]
+  pub fn get_fallback_directory_count(&self) -> u32 {
+    self.fallback_directories
+  }
+
+  /**
+   * Returns the number of relays listed in the current consensus, including those that cannot be used
+   * (see `getNetworkStatus().usableRelays`). Returns `0` if no consensus has been downloaded yet.
+   */
+  #[napi]
+  pub fn get_known_relay_count(&self) -> u32 {
+    self
+      .client
+      .dirmgr()
+      .netdir(Timeliness::Unchecked)
+      .map_or(0, |netdir| netdir.all_relays().count() as u32)
+  }
+
+  /**
+   * Returns when the current consensus stops being valid, as an ISO 8601 timestamp (e.g. `"2025-01-01T03:00:00Z"`).
+   * Past this time the client is operating on stale network information.
+   * Returns `null` if no consensus has been downloaded yet.
+   */
+  #[napi]
+  pub fn get_consensus_valid_until(&self) -> Option<String> {
+    let netdir = self.client.dirmgr().netdir(Timeliness::Unchecked).ok()?;
+    Some(humantime::format_rfc3339_seconds(netdir.lifetime().valid_until()).to_string())
+  }
+
+  /**
+   * Returns when the current consensus became valid, as an ISO 8601 timestamp (e.g. `"2025-01-01T00:00:00Z"`).
+   * Returns `null` if no consensus has been downloaded yet.
+   */
+  #[napi]
+  pub fn get_consensus_valid_after(&self) -> Option<String> {
+    let netdir = self.client.dirmgr().netdir(Timeliness::Unchecked).ok()?;
+    Some(humantime::format_rfc3339_seconds(netdir.lifetime().valid_after()).to_string())
+  }
+
+  /**
+   * Returns `[bytesIn, bytesOut]`: the bytes read from and written to the streams opened by this client
+   * since it was created or since `resetTransferStats()`. The totals are shared with the handles returned by
+   * `isolated()`, `isolatedGroup()` and `newCircuit()`. Onion service traffic is reported by `OnionService.statistics()`.
+   */
+  #[napi(ts_return_type = "[bigint, bigint]")]
+  pub fn total_bytes_transferred(&self) -> napi::Result<Vec<u64>> {
+    Ok(vec![
+      self.transfer.bytes_in.load(Ordering::Relaxed),
+      self.transfer.bytes_out.load(Ordering::Relaxed),
+    ])
+  }
+
+  /**
+   * Zeroes the totals returned by `totalBytesTransferred()`.
+   */
+  #[napi]
+  pub fn reset_transfer_stats(&self) {
+    self.transfer.bytes_in.store(0, Ordering::Relaxed);
+    self.transfer.bytes_out.store(0, Ordering::Relaxed);
   }
 
   /**
@@ -85,10 +892,62 @@ impl NativeTorClient {
    */
   #[napi]
   pub fn set_stream_prefs(&mut self, stream_prefs: &NativeStreamPrefs) -> &Self {
-    self.client.set_stream_prefs(stream_prefs.get());
+    *self.prefs.write().unwrap() = stream_prefs.get();
     self
   }
 
+  /**
+   * Replaces the default preferences for future connections made with this handle, like `setStreamPrefs()`.
+   * Connections that are already open or being opened are not affected.
+   */
+  #[napi]
+  pub fn reconfigure_stream_prefs(&self, prefs: &NativeStreamPrefs) -> napi::Result<()> {
+    *self.prefs.write().unwrap() = prefs.get();
+    Ok(())
+  }
+
+  /**
+   * Registers the X25519 client authorization key for an onion service in restricted discovery mode.
+   * The key is stored in the client's keystore, so later connections to that service use it to decrypt its descriptor.
+   * Replaces any key previously registered for the same service.
+   *
+   * @param onionAddress - The Onion v3 address of the service, with or without the `.onion` suffix.
+   * @param authKey - The 32-byte X25519 secret key whose public part was authorized by the service.
+   */
+  #[napi]
+  pub fn pre_authenticate(&self, onion_address: String, auth_key: Buffer) -> napi::Result<()> {
+    let hsid = parse_hsid(&onion_address)?;
+    let secret: [u8; 32] = auth_key
+      .as_ref()
+      .try_into()
+      .map_err(|_| napi::Error::from_reason("Expected a 32-byte X25519 secret key"))?;
+    let secret = HsClientDescEncSecretKey::from(curve25519::StaticSecret::from(secret));
+
+    utils::map_error(self.client.insert_service_discovery_key(
+      KeystoreSelector::Primary,
+      hsid,
+      secret,
+    ))?;
+    Ok(())
+  }
+
+  /**
+   * Removes the client authorization key registered with `preAuthenticate()` for an onion service.
+   * Does nothing if no key is registered.
+   *
+   * @param onionAddress - The Onion v3 address of the service, with or without the `.onion` suffix.
+   */
+  #[napi]
+  pub fn remove_auth(&self, onion_address: String) -> napi::Result<()> {
+    let hsid = parse_hsid(&onion_address)?;
+    utils::map_error(
+      self
+        .client
+        .remove_service_discovery_key(KeystoreSelector::Primary, hsid),
+    )?;
+    Ok(())
+  }
+
   /**
    * Creates and returns a new hidden service.
    */
@@ -97,12 +956,12 @@ impl NativeTorClient {
     &self,
     onion_service_config: &NativeOnionServiceConfig,
   ) -> napi::Result<NativeOnionService> {
-    let (service, rend_request) = utils::map_error(
-      self
-        .client
-        .launch_onion_service(utils::map_error(onion_service_config.build())?),
-    )?;
-    Ok(NativeOnionService::from_service(service, rend_request))
+    self.launch_onion_service(
+      onion_service_config.get(),
+      onion_service_config.limits(),
+      None,
+      Arc::default(),
+    )
   }
 
   /**
@@ -128,16 +987,78 @@ impl NativeTorClient {
     key_bytes.copy_from_slice(&slice[0..32]);
 
     let secret: [u8; 32] = utils::map_error(key_bytes[0..32].try_into())?;
-    let kay_pair = Keypair::from_bytes(&secret);
-    let expanded = ExpandedKeypair::from(&kay_pair);
+    self.launch_onion_service(
+      onion_service_config.get(),
+      onion_service_config.limits(),
+      Some(secret),
+      Arc::default(),
+    )
+  }
 
-    let hsid_keypair = HsIdKeypair::from(expanded);
+  /**
+   * Creates a new hidden service using the secret key of an `OnionV3` instance.
+   * The service will be reachable at `key.address`. Fails if `key.hasSecret` is `false`.
+   */
+  #[napi]
+  pub fn create_onion_service_from_onion_v3(
+    &self,
+    onion_service_config: &NativeOnionServiceConfig,
+    key: &NativeOnionV3,
+  ) -> napi::Result<NativeOnionService> {
+    if !key.has_secret() {
+      return Err(napi::Error::from_reason(
+        "The OnionV3 key has no secret key to host a service with",
+      ));
+    }
+    self.launch_onion_service(
+      onion_service_config.get(),
+      onion_service_config.limits(),
+      Some(key.secret_bytes()),
+      Arc::default(),
+    )
+  }
 
-    let (service, rend_request) = utils::map_error(self.client.launch_onion_service_with_hsid(
-      utils::map_error(onion_service_config.build())?,
-      hsid_keypair,
-    ))?;
+  /**
+   * Launches a hidden service from a config builder, using the given secret key if any
+   * or the key stored in the keystore under the service nickname otherwise.
+   * Its statistics are recorded in `counters`, which `OnionService.restart()` carries over.
+   */
+  pub fn launch_onion_service(
+    &self,
+    config: OnionServiceConfigBuilder,
+    limits: RequestLimits,
+    secret: Option<[u8; 32]>,
+    counters: Arc<ServiceCounters>,
+  ) -> napi::Result<NativeOnionService> {
+    let built = utils::map_error(config.build())?;
+
+    let (service, rend_request): (_, Box<dyn Stream<Item = RendRequest> + Send + Unpin>) =
+      match secret {
+        Some(secret) => {
+          let kay_pair = Keypair::from_bytes(&secret);
+          let expanded = ExpandedKeypair::from(&kay_pair);
+          let hsid_keypair = HsIdKeypair::from(expanded);
+
+          let (service, rend_request) = utils::map_error(
+            self
+              .client
+              .launch_onion_service_with_hsid(built, hsid_keypair),
+          )?;
+          (service, Box::new(rend_request))
+        }
+        None => {
+          let (service, rend_request) = utils::map_error(self.client.launch_onion_service(built))?;
+          (service, Box::new(rend_request))
+        }
+      };
 
-    Ok(NativeOnionService::from_service(service, rend_request))
+    Ok(NativeOnionService::from_service(
+      service,
+      rend_request,
+      config,
+      limits,
+      secret,
+      counters,
+    ))
   }
 }
@@ -1,18 +1,35 @@
+use crate::client::bootstrap_with_timeout;
 use crate::config::NativeTorClientConfig;
+use crate::logging;
 use crate::utils;
+use arti_client::config::{CfgPath, TorClientConfigBuilder};
 use arti_client::TorClient;
 use arti_client::TorClientBuilder;
+use napi::{Env, JsFunction};
+use tempfile::TempDir;
+use tor_guardmgr::fallback::FallbackList;
 use tor_rtcompat::PreferredRuntime;
 
 #[napi(js_name = "TorClientBuilder")]
 pub struct NativeTorClientBuilder {
   builder: TorClientBuilder<PreferredRuntime>,
+  config: TorClientConfigBuilder,
+  temp_dir: Option<TempDir>,
+  bootstrap_timeout: Option<u32>,
+  connect_timeout: Option<u32>,
+  /// Options set with the `with*` methods and `addExtraConfig()`, re-applied on top of the config passed to `config()`.
+  overrides: Vec<(String, serde_json::Value)>,
 }
 
 impl Default for NativeTorClientBuilder {
   fn default() -> Self {
     Self {
       builder: TorClient::builder(),
+      config: TorClientConfigBuilder::default(),
+      temp_dir: None,
+      bootstrap_timeout: None,
+      connect_timeout: None,
+      overrides: Vec::new(),
     }
   }
 }
@@ -30,15 +47,12 @@ impl NativeTorClientBuilder {
    */
   #[napi(constructor)]
   pub fn new(config: Option<&NativeTorClientConfig>) -> napi::Result<Self> {
-    let config = config
-      .map(|c| c.build())
-      .unwrap_or_else(|| NativeTorClientConfig::default().build());
-
-    let config = utils::map_error(config)?;
-
-    Ok(Self {
-      builder: TorClient::builder().config(config),
-    })
+    let mut builder = Self::default();
+    if let Some(config) = config {
+      builder.config = config.get();
+    }
+    builder.apply_config()?;
+    Ok(builder)
   }
 
   /**
@@ -55,18 +69,283 @@ impl NativeTorClientBuilder {
     Self::new(config)
   }
 
+  /**
+   * Constructs a new `TorClientBuilder` from an arti TOML configuration file (e.g. `arti.toml`).
+   * Sections that do not configure the client, such as `[proxy]` or `[logging]`, are ignored.
+   *
+   * @param path - Path to the configuration file.
+   * @throws If the file cannot be read, is not valid TOML, or does not describe a valid configuration.
+   */
+  #[napi(factory)]
+  pub fn with_config_file(path: String) -> napi::Result<Self> {
+    let text = std::fs::read_to_string(&path).map_err(|err| {
+      napi::Error::from_reason(format!("Failed to read config file {path}: {err}"))
+    })?;
+
+    let config: TorClientConfigBuilder = toml::from_str(&text).map_err(|err| {
+      napi::Error::from_reason(format!("Failed to parse config file {path}: {err}"))
+    })?;
+
+    config
+      .build()
+      .map_err(|err| napi::Error::from_reason(format!("Invalid config file {path}: {err}")))?;
+
+    let mut builder = Self {
+      config,
+      ..Self::default()
+    };
+    builder.apply_config()?;
+    Ok(builder)
+  }
+
   /**
    * Set the configuration for the TorClient under construction.
    * If not called, then a compiled-in default configuration will be used.
+   * Options already set with `withNoDiskState()`, `withDirectoryCache()`, `withStateDir()` or `addExtraConfig()`
+   * are kept and take precedence over the given configuration.
+   *
+   * @throws If one of those options is not valid with the given configuration.
    */
   #[napi]
   pub fn config(&mut self, config: &NativeTorClientConfig) -> napi::Result<&Self> {
-    let config = utils::map_error(config.build())?;
-    self.builder = self.builder.clone().config(config);
+    let mut config = config.get();
+    for (key, value) in &self.overrides {
+      config = with_config_value(&config, key, value.clone())?;
+    }
+
+    self.config = config;
+    self.apply_config()?;
+    Ok(self)
+  }
+
+  /**
+   * Stores all state and cache files in a fresh temporary directory instead of the configured paths.
+   * The directory is deleted when this builder is garbage collected, so keep it alive while clients created from it are in use.
+   */
+  #[napi]
+  pub fn with_no_disk_state(&mut self) -> napi::Result<&Self> {
+    let temp_dir = tempfile::tempdir()?;
+    let path = |name: &str| CfgPath::new_literal(temp_dir.path().join(name));
+
+    self.set_override("storage.cache_dir", path_value(path("cache"))?)?;
+    self.set_override("storage.state_dir", path_value(path("state"))?)?;
+    self.temp_dir = Some(temp_dir);
     Ok(self)
   }
 
-  pub async fn build(&self) -> Result<TorClient<PreferredRuntime>, arti_client::Error> {
-    self.builder.create_bootstrapped().await
+  /**
+   * Sets the directory where cached network information is stored, like `TorClientConfig.storage.cacheDir()`.
+   * Reusing it between runs lets clients bootstrap from the cached consensus instead of downloading it again.
+   *
+   * @param cacheDir - Path of the cache directory. `~` and variables such as `${ARTI_CACHE}` are expanded.
+   */
+  #[napi]
+  pub fn with_directory_cache(&mut self, cache_dir: String) -> napi::Result<&Self> {
+    self.set_override("storage.cache_dir", path_value(CfgPath::new(cache_dir))?)?;
+    Ok(self)
+  }
+
+  /**
+   * Sets the directory where persistent state, such as guard selection, is stored, like `TorClientConfig.storage.stateDir()`.
+   *
+   * @param stateDir - Path of the state directory. `~` and variables such as `${ARTI_LOCAL_DATA}` are expanded.
+   */
+  #[napi]
+  pub fn with_state_dir(&mut self, state_dir: String) -> napi::Result<&Self> {
+    self.set_override("storage.state_dir", path_value(CfgPath::new(state_dir))?)?;
+    Ok(self)
+  }
+
+  /**
+   * Makes `TorClient.create()` fail if bootstrapping does not complete within the given time.
+   *
+   * @param millis - Maximum bootstrap duration in milliseconds.
+   */
+  #[napi]
+  pub fn bootstrap_timeout(&mut self, millis: u32) -> &Self {
+    self.bootstrap_timeout = Some(millis);
+    self
+  }
+
+  /**
+   * Sets the default time limit for `TorClient.connect()` on clients created from this builder.
+   * A timeout passed to `connect()` takes precedence.
+   *
+   * @param millis - Maximum connection duration in milliseconds.
+   */
+  #[napi]
+  pub fn connect_timeout(&mut self, millis: u32) -> &Self {
+    self.connect_timeout = Some(millis);
+    self
+  }
+
+  /**
+   * Sets the most verbose level of Arti's log records forwarded to the `onLog()` listener.
+   * Defaults to `"info"`. Logging is process-wide, so this affects every client.
+   *
+   * @param level - One of `"trace"`, `"debug"`, `"info"`, `"warn"`, `"error"` or `"off"`.
+   * @throws If the level is not valid.
+   */
+  #[napi]
+  pub fn set_log_level(&mut self, level: String) -> napi::Result<&Self> {
+    logging::set_level(&level)?;
+    Ok(self)
   }
+
+  /**
+   * Registers a listener for Arti's log records, replacing any previous one.
+   * Logging is process-wide, so the listener receives records from every client.
+   *
+   * @param callback - Called with every record at or above the level set by `setLogLevel()`.
+   */
+  #[napi(ts_args_type = "callback: (record: LogRecord) => void")]
+  pub fn on_log(&mut self, env: Env, callback: JsFunction) -> napi::Result<&Self> {
+    logging::set_listener(&env, callback)?;
+    Ok(self)
+  }
+
+  /**
+   * Sets an arbitrary arti configuration option, as an escape hatch for options not exposed by `TorClientConfig`.
+   *
+   * @param key - Dotted path of the option, as in `arti.toml`, e.g. `"channel.padding"` or `"address_filter.allow_onion_addrs"`.
+   * @param value - The value as a TOML literal, e.g. `'"reduced"'`, `'true'` or `'[1, 2]'`.
+   * @throws If the value is not valid TOML, the key is unknown, or the resulting configuration is invalid.
+   */
+  #[napi]
+  pub fn add_extra_config(&mut self, key: String, value: String) -> napi::Result<&Self> {
+    let value = toml::from_str::<toml::Table>(&format!("value = {value}"))
+      .ok()
+      .and_then(|mut table| table.remove("value"))
+      .ok_or_else(|| napi::Error::from_reason(format!("Invalid TOML value for {key}: {value}")))?;
+
+    let value =
+      serde_json::to_value(value).map_err(|err| napi::Error::from_reason(err.to_string()))?;
+
+    self.set_override(&key, value)?;
+    Ok(self)
+  }
+
+  pub fn default_connect_timeout(&self) -> Option<u32> {
+    self.connect_timeout
+  }
+
+  /// Number of fallback directory caches in the configuration, or `0` if it is invalid.
+  pub fn fallback_directory_count(&self) -> u32 {
+    self.config.build().map_or(0, |config| {
+      let fallbacks: &FallbackList = config.as_ref();
+      fallbacks.len() as u32
+    })
+  }
+
+  pub async fn build(&self) -> napi::Result<TorClient<PreferredRuntime>> {
+    match self.bootstrap_timeout {
+      Some(timeout_ms) => {
+        let client = utils::map_error(self.builder.create_unbootstrapped_async().await)?;
+        bootstrap_with_timeout(&client, timeout_ms).await?;
+        Ok(client)
+      }
+      None => utils::map_error(self.builder.create_bootstrapped().await),
+    }
+  }
+
+  pub async fn build_unbootstrapped(&self) -> napi::Result<TorClient<PreferredRuntime>> {
+    utils::map_error(self.builder.create_unbootstrapped_async().await)
+  }
+}
+
+impl NativeTorClientBuilder {
+  /// Sets the option at a dotted path and remembers it, so that `config()` does not discard it.
+  fn set_override(&mut self, key: &str, value: serde_json::Value) -> napi::Result<()> {
+    self.config = with_config_value(&self.config, key, value.clone())?;
+    self.overrides.retain(|(existing, _)| existing != key);
+    self.overrides.push((key.to_string(), value));
+    self.apply_config()
+  }
+
+  fn apply_config(&mut self) -> napi::Result<()> {
+    let config = utils::map_error(self.config.build())?;
+    self.builder = self.builder.clone().config(config);
+    Ok(())
+  }
+}
+
+/// Returns a copy of `config` with the option at a dotted path such as `"channel.padding"` set to `value`.
+fn with_config_value(
+  config: &TorClientConfigBuilder,
+  key: &str,
+  value: serde_json::Value,
+) -> napi::Result<TorClientConfigBuilder> {
+  let mut current = config_value(config)?;
+  set_config_value(&mut current, key, value)?;
+  let config: TorClientConfigBuilder = serde_json::from_value(current)
+    .map_err(|err| napi::Error::from_reason(format!("Invalid value for {key}: {err}")))?;
+
+  // Arti ignores unknown keys when deserializing, so check that the option survived the round trip.
+  if !has_config_value(&config_value(&config)?, key) {
+    return Err(napi::Error::from_reason(format!(
+      "Unknown config key: {key}"
+    )));
+  }
+  config
+    .build()
+    .map_err(|err| napi::Error::from_reason(format!("Invalid value for {key}: {err}")))?;
+
+  Ok(config)
+}
+
+fn path_value(path: CfgPath) -> napi::Result<serde_json::Value> {
+  serde_json::to_value(path).map_err(|err| napi::Error::from_reason(err.to_string()))
+}
+
+/// Serializes the config builder, leaving out unset options so it can be deserialized again.
+fn config_value(config: &TorClientConfigBuilder) -> napi::Result<serde_json::Value> {
+  let mut value =
+    serde_json::to_value(config).map_err(|err| napi::Error::from_reason(err.to_string()))?;
+  remove_nulls(&mut value);
+  Ok(value)
+}
+
+fn remove_nulls(value: &mut serde_json::Value) {
+  match value {
+    serde_json::Value::Object(map) => {
+      map.retain(|_, value| !value.is_null());
+      map.values_mut().for_each(remove_nulls);
+    }
+    serde_json::Value::Array(items) => items.iter_mut().for_each(remove_nulls),
+    _ => {}
+  }
+}
+
+/// Sets the value at a dotted path such as `"channel.padding"`, creating intermediate tables.
+fn set_config_value(
+  config: &mut serde_json::Value,
+  key: &str,
+  value: serde_json::Value,
+) -> napi::Result<()> {
+  let invalid_key = || napi::Error::from_reason(format!("Invalid config key: {key}"));
+
+  let mut target = config;
+  for part in key.split('.') {
+    if part.is_empty() {
+      return Err(invalid_key());
+    }
+    if target.is_null() {
+      *target = serde_json::Value::Object(Default::default());
+    }
+    target = target
+      .as_object_mut()
+      .ok_or_else(invalid_key)?
+      .entry(part)
+      .or_insert(serde_json::Value::Null);
+  }
+
+  *target = value;
+  Ok(())
+}
+
+fn has_config_value(config: &serde_json::Value, key: &str) -> bool {
+  key
+    .split('.')
+    .try_fold(config, |target, part| target.get(part))
+    .is_some_and(|value| !value.is_null())
 }
@@ -4,9 +4,100 @@ use std::time::Duration;
 
 use arti_client::config::{CfgPath, ConfigBuildError, TorClientConfigBuilder};
 use arti_client::TorClientConfig;
+use tor_dirmgr::DownloadScheduleBuilder;
 
 use crate::utils;
 
+/// Highest value accepted by Arti for the channel padding timeouts.
+const MAX_PADDING_TIMEOUT_MILLIS: u32 = 60_000;
+
+#[napi]
+pub struct ConfigAddressFilter {
+  config: Rc<RefCell<TorClientConfigBuilder>>,
+}
+
+#[napi]
+impl ConfigAddressFilter {
+  /**
+   * Should we allow attempts to make Tor connections to local addresses?
+   * This option is off by default, since (by default) Tor exits will always reject connections to such addresses.
+   */
+  #[napi]
+  pub fn allow_local_addrs(&mut self, value: bool) -> &Self {
+    self
+      .config
+      .borrow_mut()
+      .address_filter()
+      .allow_local_addrs(value);
+    self
+  }
+
+  /**
+   * Should we allow attempts to connect to hidden services (`.onion` services)?
+   * This option is on by default.
+   */
+  #[napi]
+  pub fn allow_onion_addrs(&mut self, value: bool) -> &Self {
+    self
+      .config
+      .borrow_mut()
+      .address_filter()
+      .allow_onion_addrs(value);
+    self
+  }
+}
+
+#[napi]
+pub struct ConfigChannel {
+  config: Rc<RefCell<TorClientConfigBuilder>>,
+}
+
+#[napi]
+impl ConfigChannel {
+  /**
+   * How much channel padding to send, to make traffic analysis harder.
+   */
+  #[napi]
+  pub fn padding_level(&mut self, level: PaddingLevel) -> &Self {
+    self.config.borrow_mut().channel().padding(level.napi());
+    self
+  }
+
+  /**
+   * How long a channel may stay idle before a padding cell is sent to keep it alive.
+   * Values above 60 seconds are capped.
+   *
+   * This writes the `nf_ito_low`, `nf_ito_high`, `nf_ito_low_reduced` and `nf_ito_high_reduced` overrides of `netParams`,
+   * which otherwise pick a random interval between 1.5 and 9.5 seconds (9 and 14 seconds with reduced padding).
+   * They are listed by `netParams.listOverrides()` and removed by `netParams.clearOverrides()`.
+   *
+   * @throws If `millis` is `0`, which Arti treats as disabling padding; use `paddingLevel(PaddingLevel.None)` instead.
+   */
+  #[napi]
+  pub fn padding_keep_alive_interval(&mut self, millis: u32) -> napi::Result<&Self> {
+    if millis == 0 {
+      return Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        "Padding keep-alive interval must be greater than 0; use paddingLevel(PaddingLevel.None) to disable padding"
+          .to_string(),
+      ));
+    }
+
+    let millis = millis.min(MAX_PADDING_TIMEOUT_MILLIS) as i32;
+    let mut config = self.config.borrow_mut();
+    let net_params = config.override_net_params();
+    for key in [
+      "nf_ito_low",
+      "nf_ito_high",
+      "nf_ito_low_reduced",
+      "nf_ito_high_reduced",
+    ] {
+      net_params.insert(key.to_string(), millis);
+    }
+    Ok(self)
+  }
+}
+
 #[napi]
 pub struct ConfigCircuitTiming {
   config: Rc<RefCell<TorClientConfigBuilder>>,
@@ -161,6 +252,85 @@ impl ConfigDownloadSchedule {
       .retry_microdescs();
     self
   }
+
+  /**
+   * Sets how to retry the initial bootstrap attempt.
+   *
+   * @param initialDelayMs - Delay after the first failure, and lower bound for later randomized delays.
+   * @param maxRetries - Retries after the first attempt before giving up.
+   */
+  #[napi]
+  pub fn retry_bootstrap_with_params(&mut self, initial_delay_ms: u32, max_retries: u32) -> &Self {
+    let mut config = self.config.borrow_mut();
+    set_retry_params(
+      config.download_schedule().retry_bootstrap(),
+      initial_delay_ms,
+      max_retries,
+    );
+    self
+  }
+
+  /**
+   * Sets how to retry an authority cert download.
+   *
+   * @param initialDelayMs - Delay after the first failure, and lower bound for later randomized delays.
+   * @param maxRetries - Retries after the first attempt before giving up.
+   */
+  #[napi]
+  pub fn retry_certs_with_params(&mut self, initial_delay_ms: u32, max_retries: u32) -> &Self {
+    let mut config = self.config.borrow_mut();
+    set_retry_params(
+      config.download_schedule().retry_certs(),
+      initial_delay_ms,
+      max_retries,
+    );
+    self
+  }
+
+  /**
+   * Sets how to retry a consensus download.
+   *
+   * @param initialDelayMs - Delay after the first failure, and lower bound for later randomized delays.
+   * @param maxRetries - Retries after the first attempt before giving up.
+   */
+  #[napi]
+  pub fn retry_consensus_with_params(&mut self, initial_delay_ms: u32, max_retries: u32) -> &Self {
+    let mut config = self.config.borrow_mut();
+    set_retry_params(
+      config.download_schedule().retry_consensus(),
+      initial_delay_ms,
+      max_retries,
+    );
+    self
+  }
+
+  /**
+   * Sets how to retry a microdescriptor download.
+   *
+   * @param initialDelayMs - Delay after the first failure, and lower bound for later randomized delays.
+   * @param maxRetries - Retries after the first attempt before giving up.
+   */
+  #[napi]
+  pub fn retry_microdescs_with_params(&mut self, initial_delay_ms: u32, max_retries: u32) -> &Self {
+    let mut config = self.config.borrow_mut();
+    set_retry_params(
+      config.download_schedule().retry_microdescs(),
+      initial_delay_ms,
+      max_retries,
+    );
+    self
+  }
+}
+
+/// Arti counts attempts rather than retries, including the first one.
+fn set_retry_params(
+  schedule: &mut DownloadScheduleBuilder,
+  initial_delay_ms: u32,
+  max_retries: u32,
+) {
+  schedule
+    .attempts(max_retries.saturating_add(1))
+    .initial_delay(Duration::from_millis(initial_delay_ms as u64));
 }
 
 #[napi]
@@ -182,6 +352,38 @@ impl ConfigNetParams {
       .insert(key, value);
     self
   }
+
+  /**
+   * Removes a previously set override, restoring the consensus value for that parameter.
+   */
+  #[napi]
+  pub fn remove_override(&mut self, key: String) -> &Self {
+    self.config.borrow_mut().override_net_params().remove(&key);
+    self
+  }
+
+  /**
+   * Removes every override, restoring the consensus values for all parameters.
+   */
+  #[napi]
+  pub fn clear_overrides(&mut self) -> &Self {
+    self.config.borrow_mut().override_net_params().clear();
+    self
+  }
+
+  /**
+   * Returns the names of the currently overridden parameters.
+   */
+  #[napi]
+  pub fn list_overrides(&self) -> Vec<String> {
+    self
+      .config
+      .borrow_mut()
+      .override_net_params()
+      .keys()
+      .cloned()
+      .collect()
+  }
 }
 
 #[napi]
@@ -355,6 +557,16 @@ impl ConfigStorage {
       .enabled(tor_config::BoolOrAuto::Explicit(enabled));
     self
   }
+
+  /**
+   * Whether the keystore will be used, resolving the default when `keystore()` was not called.
+   * The keystore is always located in the `keystore` subdirectory of the state directory.
+   */
+  #[napi]
+  pub fn keystore_enabled(&self) -> napi::Result<bool> {
+    let keystore = utils::map_error(self.config.borrow_mut().storage().keystore().build())?;
+    Ok(keystore.is_enabled())
+  }
 }
 
 #[napi]
@@ -447,23 +659,39 @@ impl NativeTorClientConfig {
    */
   #[napi]
   pub fn allow_local_addrs(&mut self, value: bool) -> &Self {
-    self
-      .config
-      .borrow_mut()
-      .address_filter()
-      .allow_local_addrs(value);
+    self.address_filter().allow_local_addrs(value);
     self
   }
 
+  /**
+   * Address filter conf
+   */
+  #[napi(getter)]
+  pub fn address_filter(&self) -> ConfigAddressFilter {
+    ConfigAddressFilter {
+      config: self.config.clone(),
+    }
+  }
+
   /**
    * Padding conf
    */
   #[napi]
   pub fn padding(&mut self, level: PaddingLevel) -> &Self {
-    self.config.borrow_mut().channel().padding(level.napi());
+    self.channel().padding_level(level);
     self
   }
 
+  /**
+   * Channel conf
+   */
+  #[napi(getter)]
+  pub fn channel(&self) -> ConfigChannel {
+    ConfigChannel {
+      config: self.config.clone(),
+    }
+  }
+
   /**
    * Circuit timing conf
    */
@@ -555,4 +783,8 @@ impl NativeTorClientConfig {
   pub fn build(&self) -> Result<TorClientConfig, ConfigBuildError> {
     self.config.borrow().build()
   }
+
+  pub fn get(&self) -> TorClientConfigBuilder {
+    self.config.borrow().clone()
+  }
 }
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use arti_client::status::BootstrapEvents;
+use futures_util::StreamExt;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsUnknown};
+
+pub const BOOTSTRAP_PROGRESS: &str = "bootstrap:progress";
+pub const BOOTSTRAP_COMPLETE: &str = "bootstrap:complete";
+pub const STREAM_CONNECTED: &str = "stream:connected";
+pub const NETWORK_STATUS: &str = "network:status";
+
+const EVENTS: [&str; 4] = [
+  BOOTSTRAP_PROGRESS,
+  BOOTSTRAP_COMPLETE,
+  STREAM_CONNECTED,
+  NETWORK_STATUS,
+];
+
+/// Argument passed to the listeners of an event.
+#[derive(Clone)]
+pub enum EventPayload {
+  None,
+  Number(f64),
+  Text(String),
+}
+
+type Listener = ThreadsafeFunction<EventPayload, ErrorStrategy::Fatal>;
+
+/// Listeners registered on a `TorClient`, shared between the handles derived from it.
+#[derive(Clone, Default)]
+pub struct ClientEvents {
+  listeners: Arc<Mutex<HashMap<String, Vec<Listener>>>>,
+}
+
+impl ClientEvents {
+  pub fn on(&self, env: &Env, event: String, callback: JsFunction) -> napi::Result<Listener> {
+    if !EVENTS.contains(&event.as_str()) {
+      return Err(napi::Error::from_reason(format!("Unknown event: {event}")));
+    }
+
+    let mut listener: Listener =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<EventPayload>| {
+        let arg: Option<JsUnknown> = match ctx.value {
+          EventPayload::None => None,
+          EventPayload::Number(value) => Some(ctx.env.create_double(value)?.into_unknown()),
+          EventPayload::Text(value) => Some(ctx.env.create_string(&value)?.into_unknown()),
+        };
+        Ok(arg.into_iter().collect::<Vec<_>>())
+      })?;
+    // Listeners must not keep the Node.js process alive on their own.
+    listener.unref(env)?;
+
+    self
+      .listeners
+      .lock()
+      .unwrap()
+      .entry(event)
+      .or_default()
+      .push(listener.clone());
+    Ok(listener)
+  }
+
+  pub fn off(&self, event: &str) {
+    self.listeners.lock().unwrap().remove(event);
+  }
+
+  pub fn emit(&self, event: &str, payload: EventPayload) {
+    if let Some(listeners) = self.listeners.lock().unwrap().get(event) {
+      for listener in listeners {
+        listener.call(payload.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    }
+  }
+
+  /// Emits bootstrap and network events for every status reported by the client, until it is dropped.
+  pub fn drive(&self, mut bootstrap_events: BootstrapEvents, mut ready: bool) {
+    let events = self.clone();
+
+    tokio::spawn(async move {
+      while let Some(status) = bootstrap_events.next().await {
+        events.emit(
+          BOOTSTRAP_PROGRESS,
+          EventPayload::Number(status.as_frac() as f64),
+        );
+        events.emit(NETWORK_STATUS, EventPayload::Text(status.to_string()));
+
+        if status.ready_for_traffic() && !ready {
+          events.emit(BOOTSTRAP_COMPLETE, EventPayload::None);
+        }
+        ready = status.ready_for_traffic();
+      }
+    });
+  }
+}
@@ -1,14 +1,31 @@
 use std::str::FromStr;
 
 use arti_client::config::onion_service::{OnionServiceConfig, OnionServiceConfigBuilder};
+use tor_hscrypto::pk::HsClientDescEncKey;
+use tor_hsservice::config::restricted_discovery::HsClientNickname;
 use tor_hsservice::HsNickname;
+use tor_llcrypto::pk::curve25519;
 
 use crate::utils;
 
+/// Limits on pending rendezvous requests. Arti has no such settings, so they are enforced by `OnionService`.
+#[derive(Clone, Copy, Default)]
+pub struct RequestLimits {
+  pub max_pending: Option<u32>,
+  pub queue_depth: Option<u32>,
+}
+
+impl RequestLimits {
+  pub fn is_limited(&self) -> bool {
+    self.max_pending.is_some() || self.queue_depth.is_some()
+  }
+}
+
 #[napi(js_name = "OnionServiceConfig")]
 #[derive(Default)]
 pub struct NativeOnionServiceConfig {
   config: OnionServiceConfigBuilder,
+  limits: RequestLimits,
 }
 
 #[napi]
@@ -23,6 +40,45 @@ impl NativeOnionServiceConfig {
     Self::new()
   }
 
+  /**
+   * Constructs a new `OnionServiceConfig` from the TOML of an arti onion service section
+   * (the contents of `[onion_services."<nickname>"]` in `arti.toml`), with the nickname as the `nickname` key.
+   * Request limits such as `serviceMaxBurstConnections` are not part of arti's configuration and start unset.
+   *
+   * @throws If the text is not valid TOML or does not describe a valid onion service configuration.
+   */
+  #[napi(factory)]
+  pub fn from_toml(toml_str: String) -> napi::Result<Self> {
+    let config: OnionServiceConfigBuilder = toml::from_str(&toml_str).map_err(|err| {
+      napi::Error::from_reason(format!("Failed to parse onion service config: {err}"))
+    })?;
+
+    config
+      .build()
+      .map_err(|err| napi::Error::from_reason(format!("Invalid onion service config: {err}")))?;
+
+    Ok(Self {
+      config,
+      ..Self::default()
+    })
+  }
+
+  /**
+   * Serializes this configuration to TOML, in the format accepted by `OnionServiceConfig.fromToml()`.
+   * Options that were never set are left out. Request limits are not included.
+   *
+   * @throws If the configuration is not valid, e.g. when no nickname was set.
+   */
+  #[napi]
+  pub fn to_toml(&self) -> napi::Result<String> {
+    self
+      .config
+      .build()
+      .map_err(|err| napi::Error::from_reason(format!("Invalid onion service config: {err}")))?;
+
+    toml::to_string(&self.config).map_err(|err| napi::Error::from_reason(err.to_string()))
+  }
+
   /**
    * The nickname used to look up this service's keys, state, configuration, etc.
    */
@@ -34,7 +90,106 @@ impl NativeOnionServiceConfig {
     Ok(())
   }
 
+  /**
+   * How many streams may be open at once on a single rendezvous circuit to this service. Defaults to 65535.
+   * Further stream requests on a circuit that reached the limit are rejected.
+   */
+  #[napi]
+  pub fn max_streams_per_circuit(&mut self, value: u32) -> &Self {
+    self.config.max_concurrent_streams_per_circuit(value);
+    self
+  }
+
+  /**
+   * Sets the clients authorized to discover this service, replacing any previously set keys.
+   * A non-empty list enables restricted discovery mode (see `requireClientAuth`).
+   *
+   * @param keys - Hex or base64 encoded X25519 descriptor encryption public keys.
+   */
+  #[napi]
+  pub fn set_authorized_clients(&mut self, keys: Vec<String>) -> napi::Result<&Self> {
+    let keys = keys
+      .iter()
+      .map(|key| parse_client_key(key))
+      .collect::<napi::Result<Vec<_>>>()?;
+
+    let restricted_discovery = self.config.restricted_discovery();
+    restricted_discovery.enabled(!keys.is_empty());
+    *restricted_discovery.static_keys().access() = keys;
+    Ok(self)
+  }
+
+  /**
+   * Toggles restricted discovery mode, in which only authorized clients can read the service descriptor.
+   * Launching fails if it is enabled without authorized clients, or disabled while clients are set.
+   */
+  #[napi]
+  pub fn require_client_auth(&mut self, enabled: bool) -> &Self {
+    self.config.restricted_discovery().enabled(enabled);
+    self
+  }
+
+  /**
+   * How many rendezvous requests may be pending at once, i.e. received but not yet accepted or rejected,
+   * including those waiting to be retrieved with `OnionService.poll()`. Requests beyond the limit are rejected
+   * as soon as they arrive and counted in `rejectedConnections`. Unlimited by default.
+   */
+  #[napi]
+  pub fn service_max_burst_connections(&mut self, n: u32) -> &Self {
+    self.limits.max_pending = Some(n);
+    self
+  }
+
+  /**
+   * How many received rendezvous requests may wait to be retrieved with `OnionService.poll()`.
+   * Requests arriving while the queue is full are rejected and counted in `rejectedConnections`.
+   * By default requests wait in an unbounded queue, or up to `serviceMaxBurstConnections` if set.
+   */
+  #[napi]
+  pub fn service_connection_queue_depth(&mut self, n: u32) -> &Self {
+    self.limits.queue_depth = Some(n);
+    self
+  }
+
   pub fn build(&self) -> Result<OnionServiceConfig, tor_config::ConfigBuildError> {
     self.config.build()
   }
+
+  pub fn get(&self) -> OnionServiceConfigBuilder {
+    self.config.clone()
+  }
+
+  pub fn limits(&self) -> RequestLimits {
+    self.limits
+  }
+}
+
+/// Parses a hex or base64 encoded X25519 client public key into an authorized client entry.
+/// The nickname is derived from the key so the same key always maps to the same entry.
+pub fn parse_client_key(key: &str) -> napi::Result<(HsClientNickname, HsClientDescEncKey)> {
+  let trimmed = key.trim();
+  let bytes: [u8; 32] = [
+    &data_encoding::HEXLOWER_PERMISSIVE,
+    &data_encoding::BASE64,
+    &data_encoding::BASE64_NOPAD,
+  ]
+  .iter()
+  .find_map(|encoding| encoding.decode(trimmed.as_bytes()).ok())
+  .and_then(|bytes| bytes.try_into().ok())
+  .ok_or(napi::Error::from_reason(format!(
+    "Invalid client public key: {key}"
+  )))?;
+
+  let nickname = utils::map_error(HsClientNickname::from_str(&format!(
+    "client-{}",
+    data_encoding::HEXLOWER.encode(&bytes[..8])
+  )))?;
+
+  Ok((nickname, curve25519::PublicKey::from(bytes).into()))
+}
+
+/// Hex encoding of an authorized client public key.
+pub fn client_key_to_hex(key: &HsClientDescEncKey) -> String {
+  let key: curve25519::PublicKey = key.clone().into();
+  data_encoding::HEXLOWER.encode(key.as_bytes())
 }
@@ -1,20 +1,132 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures_core::Stream;
 use futures_util::stream::StreamExt;
+use napi::bindgen_prelude::within_runtime_if_available;
 use napi::bindgen_prelude::ObjectFinalize;
-use napi::tokio::sync::Mutex;
+use napi::tokio::sync::mpsc::{self, error::TrySendError};
+use napi::tokio::sync::{Mutex, Notify};
 use tokio::runtime::Runtime;
 use tokio_util::sync::CancellationToken;
+use tor_config::Reconfigure;
+use tor_hscrypto::pk::HsClientDescEncKey;
+use tor_hsservice::config::restricted_discovery::HsClientNickname;
+use tor_hsservice::config::{OnionServiceConfigBuilder, TokenBucketConfig};
 use tor_hsservice::{RendRequest, RunningOnionService};
 
-use crate::hs_streams_request::NativeStreamsRequest;
+use crate::client::NativeTorClient;
+use crate::hs_config::{client_key_to_hex, parse_client_key, RequestLimits};
+use crate::hs_streams_request::{NativeStreamRequest, NativeStreamsRequest};
 use crate::utils;
 
+/// Rendezvous request timestamps kept for `connectionsPerSecond()`.
+const MAX_TRACKED_ARRIVALS: usize = 10_000;
+
+/// Connection and traffic counters shared by a service and the streams it accepts.
+#[derive(Default)]
+pub struct ServiceCounters {
+  total_connections: AtomicU32,
+  rejected_connections: AtomicU32,
+  active_connections: AtomicU32,
+  bytes_in: AtomicU64,
+  bytes_out: AtomicU64,
+  arrivals: std::sync::Mutex<VecDeque<Instant>>,
+  peak_per_second: AtomicU32,
+  pending_requests: AtomicU32,
+}
+
+impl ServiceCounters {
+  /// Records a rendezvous request yielded to the application, updating the peak rate.
+  pub fn request_arrived(&self) {
+    let now = Instant::now();
+    let mut arrivals = self.arrivals.lock().unwrap();
+    if arrivals.len() == MAX_TRACKED_ARRIVALS {
+      arrivals.pop_front();
+    }
+    arrivals.push_back(now);
+
+    let last_second = count_since(&arrivals, now, Duration::from_secs(1));
+    self
+      .peak_per_second
+      .fetch_max(last_second, Ordering::Relaxed);
+  }
+
+  /// Returns the number of requests recorded within `window` of now.
+  fn arrivals_within(&self, window: Duration) -> u32 {
+    count_since(&self.arrivals.lock().unwrap(), Instant::now(), window)
+  }
+
+  /// Records a rendezvous request admitted by `limit_rend_requests`, until it is accepted or rejected.
+  fn request_admitted(&self) {
+    self.pending_requests.fetch_add(1, Ordering::Relaxed);
+  }
+
+  fn request_settled(&self) {
+    let _ = self
+      .pending_requests
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+        pending.checked_sub(1)
+      });
+  }
+
+  pub fn connection_opened(&self) {
+    self.total_connections.fetch_add(1, Ordering::Relaxed);
+    self.active_connections.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn connection_closed(&self) {
+    let _ = self
+      .active_connections
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |active| {
+        active.checked_sub(1)
+      });
+  }
+
+  pub fn connection_rejected(&self) {
+    self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn bytes_received(&self, bytes: usize) {
+    self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+  }
+
+  pub fn bytes_sent(&self, bytes: usize) {
+    self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+  }
+}
+
+fn count_since(arrivals: &VecDeque<Instant>, now: Instant, window: Duration) -> u32 {
+  let Some(start) = now.checked_sub(window) else {
+    return arrivals.len() as u32;
+  };
+  arrivals.iter().rev().take_while(|&&at| at >= start).count() as u32
+}
+
+/**
+ * Cumulative connection and traffic statistics returned by `OnionService.statistics()`.
+ */
+#[napi(object, object_from_js = false, js_name = "ServiceStats")]
+pub struct NativeServiceStats {
+  /// Number of streams accepted.
+  pub total_connections: u32,
+  /// Number of rendezvous and stream requests rejected.
+  pub rejected_connections: u32,
+  /// Number of accepted streams that are still open.
+  pub active_connections: u32,
+  /// Bytes read from accepted streams.
+  pub bytes_in: u64,
+  /// Bytes written to accepted streams.
+  pub bytes_out: u64,
+}
+
 #[napi(js_name = "RendRequest")]
 pub struct NativeRendRequest {
   request: Option<RendRequest>,
   cancel_token: CancellationToken,
+  counters: Arc<ServiceCounters>,
 }
 
 #[napi]
@@ -30,10 +142,15 @@ impl NativeRendRequest {
     ))
   }
 
-  pub fn from_rend_request(request: RendRequest, cancel_token: CancellationToken) -> Self {
+  pub fn from_rend_request(
+    request: RendRequest,
+    cancel_token: CancellationToken,
+    counters: Arc<ServiceCounters>,
+  ) -> Self {
     Self {
       request: Some(request),
       cancel_token,
+      counters,
     }
   }
 
@@ -42,24 +159,57 @@ impl NativeRendRequest {
    */
   #[napi]
   pub async unsafe fn accept(&mut self) -> napi::Result<Option<NativeStreamsRequest>> {
-    if let Some(request) = self.request.take() {
+    if let Some(request) = self.take_request() {
       let streams_request = utils::map_error(request.accept().await)?;
       Ok(Some(NativeStreamsRequest::from_streams_request(
         streams_request,
         self.cancel_token.clone(),
+        self.counters.clone(),
       )))
     } else {
       Ok(None)
     }
   }
 
+  /**
+   * Like `accept()`, but gives up if the rendezvous point cannot be reached within `timeoutMs` milliseconds.
+   * Returns `null` on timeout. The request is consumed either way: a timed-out request is abandoned without
+   * notifying the client, which is left waiting until its own timeout, and is counted as rejected.
+   * Calling `reject()` afterwards is a no-op.
+   *
+   * @param timeoutMs - Maximum time to wait for the rendezvous circuit, in milliseconds.
+   */
+  #[napi]
+  pub async unsafe fn timeout_accept(
+    &mut self,
+    timeout_ms: u32,
+  ) -> napi::Result<Option<NativeStreamsRequest>> {
+    let Some(request) = self.take_request() else {
+      return Ok(None);
+    };
+
+    let timeout = Duration::from_millis(timeout_ms as u64);
+    match tokio::time::timeout(timeout, request.accept()).await {
+      Ok(streams_request) => Ok(Some(NativeStreamsRequest::from_streams_request(
+        utils::map_error(streams_request)?,
+        self.cancel_token.clone(),
+        self.counters.clone(),
+      ))),
+      Err(_) => {
+        self.counters.connection_rejected();
+        Ok(None)
+      }
+    }
+  }
+
   /**
    * Reject this request. (The client will receive no notification.)
    */
   #[napi]
   pub async unsafe fn reject(&mut self) -> napi::Result<()> {
-    if let Some(request) = self.request.take() {
+    if let Some(request) = self.take_request() {
       utils::map_error(request.reject().await)?;
+      self.counters.connection_rejected();
       Ok(())
     } else {
       Ok(())
@@ -67,11 +217,195 @@ impl NativeRendRequest {
   }
 }
 
+impl NativeRendRequest {
+  fn take_request(&mut self) -> Option<RendRequest> {
+    let request = self.request.take()?;
+    self.counters.request_settled();
+    Some(request)
+  }
+}
+
+impl Drop for NativeRendRequest {
+  fn drop(&mut self) {
+    self.take_request();
+  }
+}
+
+/// Moves rendezvous requests into a bounded queue as soon as they arrive, rejecting those that exceed `limits`.
+fn limit_rend_requests(
+  mut rend_requests: impl Stream<Item = RendRequest> + Send + Unpin + 'static,
+  limits: RequestLimits,
+  counters: Arc<ServiceCounters>,
+  cancel_token: CancellationToken,
+) -> impl Stream<Item = RendRequest> + Send + Unpin {
+  // Pending requests include queued ones, so the queue never needs to outgrow `max_pending`.
+  let capacity = limits.queue_depth.or(limits.max_pending).unwrap_or(1);
+  let (sender, receiver) = mpsc::channel(capacity.max(1) as usize);
+
+  within_runtime_if_available(|| {
+    tokio::spawn(async move {
+      loop {
+        let rend_request = tokio::select! {
+          _ = cancel_token.cancelled() => return,
+          rend_request = rend_requests.next() => rend_request,
+        };
+        let Some(rend_request) = rend_request else {
+          return;
+        };
+
+        let over_limit = limits
+          .max_pending
+          .is_some_and(|max| counters.pending_requests.load(Ordering::Relaxed) >= max);
+        let rejected = if over_limit {
+          Some(rend_request)
+        } else {
+          counters.request_admitted();
+          match sender.try_send(rend_request) {
+            Ok(()) => None,
+            Err(TrySendError::Full(rend_request)) => {
+              counters.request_settled();
+              Some(rend_request)
+            }
+            Err(TrySendError::Closed(_)) => return,
+          }
+        };
+
+        if let Some(rend_request) = rejected {
+          let _ = rend_request.reject().await;
+          counters.connection_rejected();
+        }
+      }
+    })
+  });
+
+  Box::pin(futures_util::stream::unfold(
+    receiver,
+    |mut receiver| async move {
+      let rend_request = receiver.recv().await?;
+      Some((rend_request, receiver))
+    },
+  ))
+}
+
+type BoxedRendRequests = Arc<Mutex<Option<Box<dyn Stream<Item = RendRequest> + Unpin + Send>>>>;
+
+/// Pause flag shared by a service and its request stream, with a notifier to wake paused pollers.
+#[derive(Default)]
+struct AcceptGate {
+  paused: AtomicBool,
+  resumed: Notify,
+}
+
+impl AcceptGate {
+  fn pause(&self) {
+    self.paused.store(true, Ordering::SeqCst);
+  }
+
+  fn resume(&self) {
+    self.paused.store(false, Ordering::SeqCst);
+    self.resumed.notify_waiters();
+  }
+
+  fn is_paused(&self) -> bool {
+    self.paused.load(Ordering::SeqCst)
+  }
+
+  /// Waits until the gate is open.
+  async fn opened(&self) {
+    loop {
+      let resumed = self.resumed.notified();
+      if !self.is_paused() {
+        return;
+      }
+      resumed.await;
+    }
+  }
+}
+
+async fn next_rend_request(
+  rend_request: &BoxedRendRequests,
+  cancel_token: &CancellationToken,
+  counters: &Arc<ServiceCounters>,
+  gate: &AcceptGate,
+) -> Option<NativeRendRequest> {
+  let fut = async {
+    gate.opened().await;
+    let mut rend_request = rend_request.lock().await;
+    rend_request.as_mut()?.next().await.map(|rend_request| {
+      counters.request_arrived();
+      NativeRendRequest::from_rend_request(rend_request, cancel_token.clone(), counters.clone())
+    })
+  };
+
+  tokio::select! {
+    biased;
+
+    _ = cancel_token.cancelled() => None,
+    result = fut => result
+  }
+}
+
+#[napi(js_name = "RendRequestStream")]
+pub struct NativeRendRequestStream {
+  rend_request: BoxedRendRequests,
+  cancel_token: CancellationToken,
+  counters: Arc<ServiceCounters>,
+  gate: Arc<AcceptGate>,
+  restarted: Arc<AtomicBool>,
+}
+
+#[napi]
+impl NativeRendRequestStream {
+  /**
+   * This class cannot be constructed manually.
+   */
+  #[napi(constructor)]
+  pub fn new() -> napi::Result<Self> {
+    Err(napi::Error::new(
+      napi::Status::GenericFailure,
+      "This class cannot be constructed manually.".to_string(),
+    ))
+  }
+
+  /**
+   * Retrieves the next RendRequest.
+   * Returns `null|undefined` once the hidden service is closed.
+   *
+   * @throws If the service was restarted with `OnionService.restart()`; call `intoRequestStream()` again
+   * to receive requests from the relaunched service.
+   */
+  #[napi]
+  pub async fn next(&self) -> napi::Result<Option<NativeRendRequest>> {
+    let rend_request = next_rend_request(
+      &self.rend_request,
+      &self.cancel_token,
+      &self.counters,
+      &self.gate,
+    )
+    .await;
+
+    if rend_request.is_none() && self.restarted.load(Ordering::SeqCst) {
+      return Err(napi::Error::from_reason(
+        "Hidden service was restarted; call intoRequestStream() again",
+      ));
+    }
+    Ok(rend_request)
+  }
+}
+
 #[napi(js_name = "OnionService", custom_finalize)]
 pub struct NativeOnionService {
   service: Option<Arc<RunningOnionService>>,
-  rend_request: Arc<Mutex<Option<Box<dyn Stream<Item = RendRequest> + Unpin + Send>>>>,
+  rend_request: BoxedRendRequests,
   cancel_token: CancellationToken,
+  config: OnionServiceConfigBuilder,
+  limits: RequestLimits,
+  secret: Option<[u8; 32]>,
+  counters: Arc<ServiceCounters>,
+  gate: Arc<AcceptGate>,
+  started_at: Instant,
+  /// Set when this launch is replaced by `restart()`, so that streams taken from it can tell.
+  restarted: Arc<AtomicBool>,
 }
 
 #[napi]
@@ -90,14 +424,56 @@ impl NativeOnionService {
   pub fn from_service(
     service: Arc<RunningOnionService>,
     rend_request: impl Stream<Item = RendRequest> + Send + Unpin + 'static,
+    config: OnionServiceConfigBuilder,
+    limits: RequestLimits,
+    secret: Option<[u8; 32]>,
+    counters: Arc<ServiceCounters>,
   ) -> Self {
+    let cancel_token = CancellationToken::new();
+
+    let rend_request: Box<dyn Stream<Item = RendRequest> + Unpin + Send> = if limits.is_limited() {
+      Box::new(limit_rend_requests(
+        rend_request,
+        limits,
+        counters.clone(),
+        cancel_token.clone(),
+      ))
+    } else {
+      Box::new(rend_request)
+    };
+
     Self {
       service: Some(service),
-      rend_request: Arc::new(Mutex::new(Some(Box::new(rend_request)))),
-      cancel_token: CancellationToken::new(),
+      rend_request: Arc::new(Mutex::new(Some(rend_request))),
+      cancel_token,
+      config,
+      limits,
+      secret,
+      counters,
+      gate: Arc::new(AcceptGate::default()),
+      started_at: Instant::now(),
+      restarted: Arc::default(),
     }
   }
 
+  /**
+   * Time since the service was launched or last restarted, in milliseconds.
+   */
+  #[napi(getter)]
+  pub fn uptime_ms(&self) -> f64 {
+    self.started_at.elapsed().as_millis() as f64
+  }
+
+  /**
+   * When the service was launched or last restarted, in milliseconds since the Unix epoch.
+   */
+  #[napi(getter)]
+  pub fn started_at_ms(&self) -> f64 {
+    (SystemTime::now() - self.started_at.elapsed())
+      .duration_since(UNIX_EPOCH)
+      .map_or(0.0, |since_epoch| since_epoch.as_secs_f64() * 1000.0)
+  }
+
   /**
    * Waits until the hidden service reaches the `Running` state.
    * If `maxTime` is provided, throws an error if the timeout is exceeded.
@@ -135,28 +511,84 @@ impl NativeOnionService {
    */
   #[napi]
   pub async fn poll(&self) -> napi::Result<NativeRendRequest> {
-    let token = self.cancel_token.clone();
-
-    let fut = async {
-      let mut rend_request = self.rend_request.lock().await;
-      if let Some(rend_request) = rend_request.as_mut() {
-        rend_request
-          .next()
-          .await
-          .map(|rend_request| {
-            NativeRendRequest::from_rend_request(rend_request, self.cancel_token.clone())
-          })
-          .ok_or(napi::Error::from_reason("Hidden service was closed"))
-      } else {
-        Err(napi::Error::from_reason("Hidden service was closed"))
-      }
-    };
+    next_rend_request(
+      &self.rend_request,
+      &self.cancel_token,
+      &self.counters,
+      &self.gate,
+    )
+    .await
+    .ok_or(napi::Error::from_reason("Hidden service was closed"))
+  }
 
-    tokio::select! {
-      biased;
+  /**
+   * Stops handing out new RendRequests without shutting the service down.
+   * While paused, `poll()` and `RendRequestStream.next()` wait without consuming pending requests,
+   * which are left queued until `resumeAccepting()` is called.
+   */
+  #[napi]
+  pub fn pause_accepting(&self) {
+    self.gate.pause();
+  }
+
+  /**
+   * Resumes handing out RendRequests after `pauseAccepting()`, waking up any pending `poll()`.
+   */
+  #[napi]
+  pub fn resume_accepting(&self) {
+    self.gate.resume();
+  }
+
+  /**
+   * Whether the service is currently paused by `pauseAccepting()`.
+   */
+  #[napi(getter)]
+  pub fn is_accepting_paused(&self) -> bool {
+    self.gate.is_paused()
+  }
+
+  /**
+   * Moves the queue of incoming RendRequests into a separate `RendRequestStream`, which can be handed
+   * to the request processing loop while this object manages the service lifecycle.
+   * Afterwards `poll()` on this service fails as if it was closed; closing the service ends the stream.
+   *
+   * @throws If the requests were already moved, or a `poll()` call is in progress.
+   */
+  #[napi]
+  #[allow(clippy::wrong_self_convention)]
+  pub fn into_request_stream(&self) -> napi::Result<NativeRendRequestStream> {
+    if self.service.is_none() {
+      return Err(napi::Error::from_reason("Hidden service was closed"));
+    }
 
-      _ = token.cancelled() => Err(napi::Error::from_reason("Hidden service was closed")),
-      result = fut => utils::map_error(result)
+    let rend_request = self
+      .rend_request
+      .try_lock()
+      .map_err(|_| napi::Error::from_reason("RendRequests are being polled"))?
+      .take()
+      .ok_or(napi::Error::from_reason("RendRequests were already moved"))?;
+
+    Ok(NativeRendRequestStream {
+      rend_request: Arc::new(Mutex::new(Some(rend_request))),
+      cancel_token: self.cancel_token.clone(),
+      counters: self.counters.clone(),
+      gate: self.gate.clone(),
+      restarted: self.restarted.clone(),
+    })
+  }
+
+  /**
+   * Retrieves the next RendRequest, accepts it and returns the first StreamRequest sent over the rendezvous circuit.
+   * Shortcut for services that unconditionally accept every connection; further streams opened on the same
+   * rendezvous circuit are not delivered. Returns `null|undefined` if the circuit ends without any stream request.
+   */
+  #[napi]
+  pub async fn poll_stream(&self) -> napi::Result<Option<NativeStreamRequest>> {
+    let mut rend_request = self.poll().await?;
+
+    match unsafe { rend_request.accept().await }? {
+      Some(mut streams_request) => Ok(unsafe { streams_request.poll().await }),
+      None => Ok(None),
     }
   }
 
@@ -173,6 +605,80 @@ impl NativeOnionService {
       .and_then(|service| service.onion_address().map(|address| address.to_string()))
   }
 
+  /**
+   * Returns the nickname the service was configured with, see `OnionServiceConfig.nickname()`.
+   * It identifies the service in the keystore and state directory, and is never published.
+   */
+  #[napi]
+  pub fn get_service_id(&self) -> Option<String> {
+    self
+      .config
+      .peek_nickname()
+      .map(|nickname| nickname.to_string())
+  }
+
+  /**
+   * Describes the state of the service descriptor, derived from the overall service status:
+   * - `"pending"`: the service is starting, and no descriptor has been published yet.
+   * - `"published"`: the descriptor is up to date on the directories.
+   * - `"stale"`: the last upload failed or the introduction points changed, and the service is trying to recover.
+   * - `"failed"`: the service is broken and is no longer publishing.
+   * - `"stopped"`: the service was shut down or closed.
+   */
+  #[napi]
+  pub fn get_service_descriptor_status(&self) -> String {
+    use tor_hsservice::status::State;
+
+    let Some(service) = &self.service else {
+      return "stopped".to_string();
+    };
+
+    match service.status().state() {
+      State::Shutdown => "stopped",
+      State::Bootstrapping => "pending",
+      State::Running | State::DegradedReachable => "published",
+      State::DegradedUnreachable | State::Recovering => "stale",
+      State::Broken => "failed",
+      _ => "unknown",
+    }
+    .to_string()
+  }
+
+  /**
+   * Describes the most severe problem reported by the service, e.g. why it is `DegradedReachable` or `Recovering`.
+   * Returns `null` while the service is running normally, or once it has been closed.
+   */
+  #[napi(getter)]
+  pub fn last_error(&self) -> Option<String> {
+    use tor_hsservice::status::{Problem, State};
+
+    let status = self.service.as_ref()?.status();
+    if status.state() == State::Running {
+      return None;
+    }
+
+    Some(match status.current_problem()? {
+      Problem::Runtime(err) => err.to_string(),
+      Problem::DescriptorUpload(errors) => format!(
+        "Descriptor upload failed: {}",
+        errors
+          .iter()
+          .map(ToString::to_string)
+          .collect::<Vec<_>>()
+          .join("; ")
+      ),
+      Problem::Ipt(errors) => format!(
+        "Failed to establish introduction points: {}",
+        errors
+          .iter()
+          .map(ToString::to_string)
+          .collect::<Vec<_>>()
+          .join("; ")
+      ),
+      problem => format!("{problem:?}"),
+    })
+  }
+
   /**
    * Returns the current status of the hidden service.
    */
@@ -194,6 +700,176 @@ impl NativeOnionService {
       .unwrap_or(StateOnionService::Shutdown)
   }
 
+  /**
+   * Updates the rate limit that introduction points apply to introduction requests for this service.
+   * The new limit is sent as DoS parameters when establishing introduction points, so the
+   * running service replaces its introduction points and republishes its descriptor.
+   *
+   * @param requestsPerSecond - Sustained number of introduction requests allowed per second (rounded to an integer).
+   * @param burst - Maximum number of introduction requests allowed in a single burst.
+   */
+  #[napi]
+  pub fn set_rate_limit(&mut self, requests_per_second: f64, burst: u32) -> napi::Result<()> {
+    let service = self
+      .service
+      .as_ref()
+      .ok_or(napi::Error::from_reason("Hidden service was closed"))?;
+
+    let rate = requests_per_second.round();
+    if !rate.is_finite() || rate < 1.0 || rate > u32::MAX as f64 {
+      return Err(napi::Error::from_reason(
+        "Requests per second must be a positive number",
+      ));
+    }
+
+    let mut config = self.config.clone();
+    config.rate_limit_at_intro(Some(TokenBucketConfig::new(rate as u32, burst)));
+
+    let built = utils::map_error(config.build())?;
+    utils::map_error(service.reconfigure(built, Reconfigure::AllOrNothing))?;
+
+    self.config = config;
+    Ok(())
+  }
+
+  /**
+   * Returns the connections and bytes handled by this service since it was launched or since `resetStatistics()`,
+   * including before any `restart()`. Only streams accepted through this object are counted.
+   */
+  #[napi]
+  pub fn statistics(&self) -> napi::Result<NativeServiceStats> {
+    let counters = &self.counters;
+
+    Ok(NativeServiceStats {
+      total_connections: counters.total_connections.load(Ordering::Relaxed),
+      rejected_connections: counters.rejected_connections.load(Ordering::Relaxed),
+      active_connections: counters.active_connections.load(Ordering::Relaxed),
+      bytes_in: counters.bytes_in.load(Ordering::Relaxed),
+      bytes_out: counters.bytes_out.load(Ordering::Relaxed),
+    })
+  }
+
+  /**
+   * Returns the average number of rendezvous requests per second received over the last `windowMs` milliseconds.
+   * Only requests yielded by `poll()` or the request stream are counted, and at most the last 10000 are remembered.
+   *
+   * @param windowMs - Length of the window in milliseconds.
+   */
+  #[napi]
+  pub fn connections_per_second(&self, window_ms: u32) -> f64 {
+    if window_ms == 0 {
+      return 0.0;
+    }
+    let window = Duration::from_millis(window_ms as u64);
+    self.counters.arrivals_within(window) as f64 / window.as_secs_f64()
+  }
+
+  /**
+   * Returns the highest number of rendezvous requests received within any one-second period
+   * since the service was launched or since `resetStatistics()`.
+   */
+  #[napi]
+  pub fn peak_connections_per_second(&self) -> f64 {
+    self.counters.peak_per_second.load(Ordering::Relaxed) as f64
+  }
+
+  /**
+   * Zeroes the cumulative statistics. `activeConnections` is kept, since it counts streams that are still open.
+   */
+  #[napi]
+  pub fn reset_statistics(&self) {
+    let counters = &self.counters;
+
+    counters.total_connections.store(0, Ordering::Relaxed);
+    counters.rejected_connections.store(0, Ordering::Relaxed);
+    counters.bytes_in.store(0, Ordering::Relaxed);
+    counters.bytes_out.store(0, Ordering::Relaxed);
+    counters.peak_per_second.store(0, Ordering::Relaxed);
+  }
+
+  /**
+   * Authorizes a client to discover this service, enabling restricted discovery mode.
+   * While at least one client is authorized, the descriptor is encrypted so that only
+   * authorized clients can read it; unauthorized clients receive no usable descriptor.
+   *
+   * @param publicKeyHex - The client's hex or base64 encoded X25519 descriptor encryption public key.
+   */
+  #[napi]
+  pub fn add_authorized_client(&mut self, public_key_hex: String) -> napi::Result<()> {
+    let (nickname, key) = parse_client_key(&public_key_hex)?;
+
+    let mut config = self.config.clone();
+    let mut keys = config.restricted_discovery().static_keys().access().clone();
+    if keys.iter().any(|(_, existing)| *existing == key) {
+      return Ok(());
+    }
+    keys.push((nickname, key));
+
+    self.set_authorized_clients(config, keys)
+  }
+
+  /**
+   * Revokes a previously authorized client. Removing the last client disables restricted discovery mode,
+   * making the service discoverable by anyone who knows its address.
+   *
+   * @param publicKeyHex - The client's hex or base64 encoded X25519 descriptor encryption public key.
+   */
+  #[napi]
+  pub fn remove_authorized_client(&mut self, public_key_hex: String) -> napi::Result<()> {
+    let (_, key) = parse_client_key(&public_key_hex)?;
+
+    let mut config = self.config.clone();
+    let mut keys = config.restricted_discovery().static_keys().access().clone();
+    keys.retain(|(_, existing)| *existing != key);
+
+    self.set_authorized_clients(config, keys)
+  }
+
+  /**
+   * Returns the hex-encoded public keys of the clients authorized to discover this service.
+   */
+  #[napi]
+  pub fn list_authorized_clients(&self) -> Vec<String> {
+    self
+      .config
+      .clone()
+      .restricted_discovery()
+      .static_keys()
+      .access()
+      .iter()
+      .map(|(_, key)| client_key_to_hex(key))
+      .collect()
+  }
+
+  /**
+   * Restarts a closed or running hidden service with the configuration it was launched with.
+   * The onion address is preserved: the same secret key (or the keystore entry for the service nickname) is reused.
+   * Pending `poll()` calls are rejected, and subsequent calls receive requests from the relaunched service.
+   * A `RendRequestStream` taken with `intoRequestStream()` ends with an error; take a new one after restarting.
+   *
+   * `statistics()` and the `pauseAccepting()` state carry over to the relaunched service,
+   * while `uptimeMs` and `startedAtMs` start again from the restart.
+   *
+   * @param client - The client used to relaunch the service.
+   */
+  #[napi]
+  pub fn restart(&mut self, client: &NativeTorClient) -> napi::Result<()> {
+    self.restarted.store(true, Ordering::SeqCst);
+    self.close();
+    // Requests queued by the previous launch were dropped with it.
+    self.counters.pending_requests.store(0, Ordering::Relaxed);
+
+    let mut relaunched = client.launch_onion_service(
+      self.config.clone(),
+      self.limits,
+      self.secret,
+      self.counters.clone(),
+    )?;
+    relaunched.gate = self.gate.clone();
+    *self = relaunched;
+    Ok(())
+  }
+
   /**
    * Close the hidden service.
    */
@@ -206,6 +882,29 @@ impl NativeOnionService {
   }
 }
 
+impl NativeOnionService {
+  fn set_authorized_clients(
+    &mut self,
+    mut config: OnionServiceConfigBuilder,
+    keys: Vec<(HsClientNickname, HsClientDescEncKey)>,
+  ) -> napi::Result<()> {
+    let service = self
+      .service
+      .as_ref()
+      .ok_or(napi::Error::from_reason("Hidden service was closed"))?;
+
+    let restricted_discovery = config.restricted_discovery();
+    restricted_discovery.enabled(!keys.is_empty());
+    *restricted_discovery.static_keys().access() = keys;
+
+    let built = utils::map_error(config.build())?;
+    utils::map_error(service.reconfigure(built, Reconfigure::AllOrNothing))?;
+
+    self.config = config;
+    Ok(())
+  }
+}
+
 impl ObjectFinalize for NativeOnionService {
   fn finalize(mut self, _env: napi::Env) -> napi::Result<()> {
     self.close();
@@ -3,17 +3,22 @@ use std::sync::Arc;
 use futures_core::Stream;
 use futures_util::lock::Mutex;
 use futures_util::StreamExt;
+use napi::bindgen_prelude::Buffer;
+use napi::tokio::io::AsyncWriteExt;
+use napi::{Env, JsFunction, JsObject, JsUnknown};
 use tokio_util::sync::CancellationToken;
 use tor_cell::relaycell::msg::{Connected, End, EndReason};
 use tor_hsservice::StreamRequest;
 use tor_proto::stream::IncomingStreamRequest;
 
+use crate::hs_service::ServiceCounters;
 use crate::stream::NativeTorStream;
 use crate::utils;
 
 #[napi(js_name = "StreamRequest")]
 pub struct NativeStreamRequest {
   request: Option<StreamRequest>,
+  counters: Arc<ServiceCounters>,
 }
 
 #[napi]
@@ -29,9 +34,10 @@ impl NativeStreamRequest {
     ))
   }
 
-  pub fn from_stream_request(request: StreamRequest) -> Self {
+  pub fn from_stream_request(request: StreamRequest, counters: Arc<ServiceCounters>) -> Self {
     Self {
       request: Some(request),
+      counters,
     }
   }
 
@@ -88,12 +94,39 @@ impl NativeStreamRequest {
   pub async unsafe fn accept(&mut self) -> napi::Result<Option<NativeTorStream>> {
     if let Some(request) = self.request.take() {
       let data_stream = utils::map_error(request.accept(Connected::new_empty()).await)?;
-      Ok(Some(NativeTorStream::from_stream(data_stream)))
+      Ok(Some(NativeTorStream::from_service_stream(
+        data_stream,
+        self.counters.clone(),
+      )))
     } else {
       Ok(None)
     }
   }
 
+  /**
+   * Accept this request like `accept()`, and send `data` to the client right after the CONNECTED message,
+   * before the stream is returned. Useful for protocols where the server speaks first, such as a greeting banner.
+   */
+  #[napi]
+  pub async unsafe fn accept_with_data(
+    &mut self,
+    data: Buffer,
+  ) -> napi::Result<Option<NativeTorStream>> {
+    let Some(request) = self.request.take() else {
+      return Ok(None);
+    };
+
+    let mut data_stream = utils::map_error(request.accept(Connected::new_empty()).await)?;
+    utils::map_error(data_stream.write_all(&data).await)?;
+    utils::map_error(data_stream.flush().await)?;
+    self.counters.bytes_sent(data.len());
+
+    Ok(Some(NativeTorStream::from_service_stream(
+      data_stream,
+      self.counters.clone(),
+    )))
+  }
+
   /**
    * Reject this request, and send the client an END message.
    */
@@ -101,6 +134,7 @@ impl NativeStreamRequest {
   pub async unsafe fn reject(&mut self) -> napi::Result<()> {
     if let Some(request) = self.request.take() {
       utils::map_error(request.reject(End::new_with_reason(EndReason::DONE)).await)?;
+      self.counters.connection_rejected();
     }
     Ok(())
   }
@@ -112,15 +146,53 @@ impl NativeStreamRequest {
   pub async unsafe fn shutdown_circuit(&mut self) -> napi::Result<()> {
     if let Some(request) = self.request.take() {
       utils::map_error(request.shutdown_circuit())?;
+      self.counters.connection_rejected();
     }
     Ok(())
   }
 }
 
+/**
+ * Result of a `next()` call on the async iterable returned by `StreamsRequest.toAsyncIterable()`.
+ */
+#[napi(
+  object,
+  object_from_js = false,
+  js_name = "StreamRequestIteratorResult"
+)]
+pub struct NativeStreamRequestIteratorResult {
+  pub done: bool,
+  pub value: Option<NativeStreamRequest>,
+}
+
+type BoxedStreamRequests =
+  Arc<Mutex<Box<dyn Stream<Item = StreamRequest> + Send + Unpin + 'static>>>;
+
+async fn next_stream_request(
+  streams_request: &BoxedStreamRequests,
+  cancel_token: &CancellationToken,
+  counters: &Arc<ServiceCounters>,
+) -> Option<NativeStreamRequest> {
+  let mut lock = streams_request.lock().await;
+
+  tokio::select! {
+    biased;
+
+      _ = cancel_token.cancelled() => {
+          None
+      }
+
+      result = lock.next() => {
+          result.map(|request| NativeStreamRequest::from_stream_request(request, counters.clone()))
+      }
+  }
+}
+
 #[napi(js_name = "StreamsRequest")]
 pub struct NativeStreamsRequest {
-  streams_request: Arc<Mutex<Box<dyn Stream<Item = StreamRequest> + Send + Unpin + 'static>>>,
+  streams_request: BoxedStreamRequests,
   cancel_token: CancellationToken,
+  counters: Arc<ServiceCounters>,
 }
 
 unsafe impl Send for NativeStreamsRequest {}
@@ -142,10 +214,12 @@ impl NativeStreamsRequest {
   pub fn from_streams_request(
     streams_request: impl Stream<Item = StreamRequest> + Send + Unpin + 'static,
     cancel_token: CancellationToken,
+    counters: Arc<ServiceCounters>,
   ) -> Self {
     Self {
       streams_request: Arc::new(Mutex::new(Box::new(streams_request))),
       cancel_token,
+      counters,
     }
   }
 
@@ -154,19 +228,93 @@ impl NativeStreamsRequest {
    */
   #[napi]
   pub async unsafe fn poll(&mut self) -> Option<NativeStreamRequest> {
-    let cancel_token = self.cancel_token.clone();
-    let mut lock = self.streams_request.lock().await;
+    next_stream_request(&self.streams_request, &self.cancel_token, &self.counters).await
+  }
 
-    tokio::select! {
-      biased;
+  /**
+   * Returns a `StreamsRequest` that only yields `Begin` requests for one of the given ports.
+   * Other requests are rejected with an END message as they arrive.
+   * The returned object reads from this one, which should no longer be polled directly.
+   *
+   * @param ports - Virtual ports to accept requests for.
+   */
+  #[napi]
+  pub fn filter_by_port(&self, ports: Vec<u16>) -> NativeStreamsRequest {
+    let counters = self.counters.clone();
+    let requests =
+      futures_util::stream::unfold(self.streams_request.clone(), |source| async move {
+        let request = source.lock().await.next().await;
+        request.map(|request| (request, source))
+      })
+      .filter_map(move |request| {
+        let allowed = matches!(
+          request.request(),
+          IncomingStreamRequest::Begin(begin) if ports.contains(&begin.port())
+        );
+        let counters = counters.clone();
 
-        _ = cancel_token.cancelled() => {
-            None
+        async move {
+          if allowed {
+            return Some(request);
+          }
+          if request
+            .reject(End::new_with_reason(EndReason::DONE))
+            .await
+            .is_ok()
+          {
+            counters.connection_rejected();
+          }
+          None
         }
+      });
 
-        result = lock.next() => {
-            result.map(NativeStreamRequest::from_stream_request)
-        }
-    }
+    Self::from_streams_request(
+      Box::pin(requests),
+      self.cancel_token.clone(),
+      self.counters.clone(),
+    )
+  }
+
+  /**
+   * Returns an async iterable that yields each incoming StreamRequest until the stream ends or is closed.
+   *
+   * @example
+   * ```ts
+   * for await (const streamRequest of streamsRequest.toAsyncIterable()) {
+   *   const stream = await streamRequest.accept();
+   * }
+   * ```
+   */
+  #[napi(ts_return_type = "AsyncIterableIterator<StreamRequest>")]
+  pub fn to_async_iterable(&self, env: Env) -> napi::Result<JsObject> {
+    let streams_request = self.streams_request.clone();
+    let cancel_token = self.cancel_token.clone();
+    let counters = self.counters.clone();
+
+    let next = env.create_function_from_closure("next", move |ctx| {
+      let streams_request = streams_request.clone();
+      let cancel_token = cancel_token.clone();
+      let counters = counters.clone();
+      ctx.env.spawn_future(async move {
+        let value = next_stream_request(&streams_request, &cancel_token, &counters).await;
+        Ok(NativeStreamRequestIteratorResult {
+          done: value.is_none(),
+          value,
+        })
+      })
+    })?;
+    let iterator_self =
+      env.create_function_from_closure("[Symbol.asyncIterator]", |ctx| ctx.this::<JsObject>())?;
+
+    let async_iterator = env
+      .get_global()?
+      .get_named_property::<JsFunction>("Symbol")?
+      .coerce_to_object()?
+      .get_named_property::<JsUnknown>("asyncIterator")?;
+
+    let mut iterable = env.create_object()?;
+    iterable.set_named_property("next", next)?;
+    iterable.set_property(async_iterator, iterator_self)?;
+    Ok(iterable)
   }
 }
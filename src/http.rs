@@ -0,0 +1,242 @@
+use napi::bindgen_prelude::Buffer;
+use napi::tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+const MAX_LINE_LENGTH: u64 = 64 * 1024;
+const MAX_BODY_LENGTH: u64 = 64 * 1024 * 1024;
+
+#[napi(js_name = "HttpResponse")]
+pub struct NativeHttpResponse {
+  status: u16,
+  reason: String,
+  headers: Vec<(String, String)>,
+  body: Vec<u8>,
+}
+
+#[napi]
+impl NativeHttpResponse {
+  /**
+   * This class cannot be constructed manually.
+   */
+  #[napi(constructor)]
+  pub fn new() -> napi::Result<Self> {
+    Err(napi::Error::new(
+      napi::Status::GenericFailure,
+      "This class cannot be constructed manually.".to_string(),
+    ))
+  }
+
+  /**
+   * Parses a complete HTTP/1.x response held in memory, e.g. one read with `TorStream.readAll()`,
+   * the same way as `TorStream.readHttpResponse()`. Bytes after the response are ignored.
+   *
+   * @throws If the data is not a valid HTTP/1.x response.
+   */
+  #[napi(factory)]
+  pub async fn parse(data: Buffer) -> napi::Result<Self> {
+    read_response(&mut data.as_ref()).await
+  }
+
+  /**
+   * The status code, e.g. `200`.
+   */
+  #[napi(getter)]
+  pub fn status(&self) -> u16 {
+    self.status
+  }
+
+  /**
+   * The reason phrase, e.g. `"OK"`.
+   */
+  #[napi(getter)]
+  pub fn reason(&self) -> String {
+    self.reason.clone()
+  }
+
+  /**
+   * The response headers as `[name, value]` pairs, in the order they were received.
+   */
+  #[napi(getter)]
+  pub fn headers(&self) -> Vec<Vec<String>> {
+    self
+      .headers
+      .iter()
+      .map(|(name, value)| vec![name.clone(), value.clone()])
+      .collect()
+  }
+
+  /**
+   * The decoded response body.
+   */
+  #[napi(getter)]
+  pub fn body(&self) -> Buffer {
+    Buffer::from(self.body.clone())
+  }
+
+  /**
+   * Returns the value of the first header with the given name, compared case-insensitively.
+   */
+  #[napi]
+  pub fn header(&self, name: String) -> Option<String> {
+    self
+      .headers
+      .iter()
+      .find(|(header, _)| header.eq_ignore_ascii_case(&name))
+      .map(|(_, value)| value.clone())
+  }
+}
+
+fn invalid(message: &str) -> napi::Error {
+  napi::Error::from_reason(format!("Invalid HTTP response: {message}"))
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> napi::Result<String> {
+  let mut line = Vec::new();
+  let n = reader
+    .take(MAX_LINE_LENGTH)
+    .read_until(b'\n', &mut line)
+    .await?;
+
+  if n == 0 || line.last() != Some(&b'\n') {
+    return Err(invalid("unexpected end of headers"));
+  }
+
+  line.pop();
+  if line.last() == Some(&b'\r') {
+    line.pop();
+  }
+
+  String::from_utf8(line).map_err(|_| invalid("header is not valid UTF-8"))
+}
+
+async fn read_headers<R: AsyncBufRead + Unpin>(
+  reader: &mut R,
+) -> napi::Result<Vec<(String, String)>> {
+  let mut headers = Vec::new();
+
+  loop {
+    let line = read_line(reader).await?;
+    if line.is_empty() {
+      return Ok(headers);
+    }
+
+    let (name, value) = line
+      .split_once(':')
+      .ok_or_else(|| invalid("malformed header"))?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+      return Err(invalid("malformed header"));
+    }
+
+    headers.push((name.to_string(), value.trim().to_string()));
+  }
+}
+
+/// Appends exactly `length` bytes to `body`, without trusting `length` for the allocation.
+async fn read_body_part<R: AsyncBufRead + Unpin>(
+  reader: &mut R,
+  body: &mut Vec<u8>,
+  length: u64,
+) -> napi::Result<()> {
+  let end = (body.len() as u64)
+    .checked_add(length)
+    .filter(|end| *end <= MAX_BODY_LENGTH)
+    .ok_or_else(|| invalid("body too large"))?;
+
+  reader.take(length).read_to_end(body).await?;
+  if body.len() as u64 != end {
+    return Err(invalid("unexpected end of body"));
+  }
+  Ok(())
+}
+
+async fn read_chunked<R: AsyncBufRead + Unpin>(reader: &mut R) -> napi::Result<Vec<u8>> {
+  let mut body = Vec::new();
+
+  loop {
+    let line = read_line(reader).await?;
+    let size = line.split(';').next().unwrap_or_default().trim();
+    let size = u64::from_str_radix(size, 16).map_err(|_| invalid("malformed chunk size"))?;
+
+    if size == 0 {
+      // Trailers are read and discarded.
+      read_headers(reader).await?;
+      return Ok(body);
+    }
+
+    read_body_part(reader, &mut body, size).await?;
+
+    if !read_line(reader).await?.is_empty() {
+      return Err(invalid("malformed chunk"));
+    }
+  }
+}
+
+/// Reads a complete HTTP/1.1 response: status line, headers and body.
+/// Interim `1xx` responses other than `101 Switching Protocols` are skipped.
+/// The body is delimited by `Transfer-Encoding: chunked`, `Content-Length` or the end of the stream, in that order,
+/// and rejected if it is larger than `MAX_BODY_LENGTH`.
+pub async fn read_response<R: AsyncBufRead + Unpin>(
+  reader: &mut R,
+) -> napi::Result<NativeHttpResponse> {
+  let (status, reason, headers) = loop {
+    let status_line = read_line(reader).await?;
+    let mut parts = status_line.splitn(3, ' ');
+
+    let version = parts.next().unwrap_or_default();
+    if !version.starts_with("HTTP/1.") {
+      return Err(invalid("malformed status line"));
+    }
+
+    let status = parts
+      .next()
+      .and_then(|status| status.parse::<u16>().ok())
+      .filter(|status| (100..1000).contains(status))
+      .ok_or_else(|| invalid("malformed status code"))?;
+    let reason = parts.next().unwrap_or_default().to_string();
+
+    let headers = read_headers(reader).await?;
+    if !(100..200).contains(&status) || status == 101 {
+      break (status, reason, headers);
+    }
+  };
+  let header = |name: &str| {
+    headers
+      .iter()
+      .find(|(header, _)| header.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  };
+
+  let body = if (100..200).contains(&status) || status == 204 || status == 304 {
+    Vec::new()
+  } else if header("transfer-encoding").is_some_and(|value| {
+    value
+      .rsplit(',')
+      .next()
+      .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+  }) {
+    read_chunked(reader).await?
+  } else if let Some(length) = header("content-length") {
+    let length = length
+      .parse::<u64>()
+      .map_err(|_| invalid("malformed Content-Length"))?;
+    let mut body = Vec::new();
+    read_body_part(reader, &mut body, length).await?;
+    body
+  } else {
+    let mut body = Vec::new();
+    reader
+      .take(MAX_BODY_LENGTH + 1)
+      .read_to_end(&mut body)
+      .await?;
+    if body.len() as u64 > MAX_BODY_LENGTH {
+      return Err(invalid("body too large"));
+    }
+    body
+  };
+
+  Ok(NativeHttpResponse {
+    status,
+    reason,
+    headers,
+    body,
+  })
+}
@@ -1,12 +1,17 @@
 #![deny(clippy::all)]
 
+mod cancel_token;
 mod client;
 mod client_builder;
 mod config;
+mod events;
 mod hs_config;
 mod hs_onion_v3;
 mod hs_service;
 mod hs_streams_request;
+mod http;
+mod logging;
+mod proxy;
 mod stream;
 mod stream_prefs;
 mod utils;
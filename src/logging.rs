@@ -0,0 +1,153 @@
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{reload, Layer, Registry};
+
+/**
+ * A log record emitted by Arti.
+ */
+#[napi(object, object_from_js = false, js_name = "LogRecord")]
+pub struct NativeLogRecord {
+  /**
+   * One of `"trace"`, `"debug"`, `"info"`, `"warn"` or `"error"`.
+   */
+  pub level: String,
+  /**
+   * The module that emitted the record, e.g. `"tor_guardmgr::guard"`.
+   */
+  pub target: String,
+  /**
+   * The message, followed by any structured fields as `key=value`.
+   */
+  pub message: String,
+  /**
+   * When the record was emitted, in milliseconds since the Unix epoch.
+   */
+  pub timestamp_ms: f64,
+}
+
+type LogListener = ThreadsafeFunction<NativeLogRecord, ErrorStrategy::Fatal>;
+
+/// Process-wide logger, installed as the global `tracing` subscriber on first use.
+struct Logger {
+  level: reload::Handle<LevelFilter, Registry>,
+  listener: Mutex<Option<LogListener>>,
+}
+
+static LOGGER: OnceLock<napi::Result<Logger>> = OnceLock::new();
+
+fn logger() -> napi::Result<&'static Logger> {
+  LOGGER
+    .get_or_init(|| {
+      let (filter, level) = reload::Layer::new(LevelFilter::INFO);
+      let subscriber = Registry::default().with(filter).with(JsLayer);
+      tracing::subscriber::set_global_default(subscriber)
+        .map_err(|err| napi::Error::from_reason(format!("Failed to install logger: {err}")))?;
+
+      Ok(Logger {
+        level,
+        listener: Mutex::new(None),
+      })
+    })
+    .as_ref()
+    .map_err(|err| napi::Error::from_reason(err.reason.clone()))
+}
+
+/// Parses one of `"trace"`, `"debug"`, `"info"`, `"warn"`, `"error"` or `"off"`.
+fn parse_level(level: &str) -> napi::Result<LevelFilter> {
+  match level {
+    "trace" => Ok(LevelFilter::TRACE),
+    "debug" => Ok(LevelFilter::DEBUG),
+    "info" => Ok(LevelFilter::INFO),
+    "warn" => Ok(LevelFilter::WARN),
+    "error" => Ok(LevelFilter::ERROR),
+    "off" => Ok(LevelFilter::OFF),
+    _ => Err(napi::Error::from_reason(format!(
+      "Invalid log level: {level}. Expected one of trace, debug, info, warn, error, off"
+    ))),
+  }
+}
+
+/// Sets the most verbose level forwarded to the log listener.
+pub fn set_level(level: &str) -> napi::Result<()> {
+  let level = parse_level(level)?;
+  crate::utils::map_error(logger()?.level.reload(level))
+}
+
+/// Replaces the log listener. It does not keep the Node.js process alive on its own.
+pub fn set_listener(env: &Env, callback: JsFunction) -> napi::Result<()> {
+  let mut listener: LogListener = callback
+    .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<NativeLogRecord>| {
+      Ok(vec![ctx.value])
+    })?;
+  listener.unref(env)?;
+
+  *logger()?.listener.lock().unwrap() = Some(listener);
+  Ok(())
+}
+
+/// Forwards every enabled event to the log listener.
+struct JsLayer;
+
+impl<S: Subscriber> Layer<S> for JsLayer {
+  fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    let Some(Ok(logger)) = LOGGER.get() else {
+      return;
+    };
+    let listener = logger.listener.lock().unwrap();
+    let Some(listener) = listener.as_ref() else {
+      return;
+    };
+
+    let mut visitor = MessageVisitor::default();
+    event.record(&mut visitor);
+
+    let metadata = event.metadata();
+    let timestamp_ms = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0);
+    listener.call(
+      NativeLogRecord {
+        level: metadata.level().as_str().to_ascii_lowercase(),
+        target: metadata.target().to_string(),
+        message: visitor.message + &visitor.fields,
+        timestamp_ms,
+      },
+      ThreadsafeFunctionCallMode::NonBlocking,
+    );
+  }
+}
+
+/// Formats an event as its message followed by ` key=value` for every other field.
+#[derive(Default)]
+struct MessageVisitor {
+  message: String,
+  fields: String,
+}
+
+impl Visit for MessageVisitor {
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    if field.name() == "message" {
+      let _ = write!(self.message, "{value:?}");
+    } else {
+      let _ = write!(self.fields, " {}={value:?}", field.name());
+    }
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    if field.name() == "message" {
+      self.message.push_str(value);
+    } else {
+      let _ = write!(self.fields, " {}={value}", field.name());
+    }
+  }
+}
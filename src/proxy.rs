@@ -0,0 +1,204 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use napi::bindgen_prelude::ObjectFinalize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::io::InspectReader;
+use tokio_util::sync::CancellationToken;
+
+use crate::client::{ClientConnector, NativeTorClient};
+
+const SOCKS_VERSION: u8 = 0x05;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xff;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+const REPLY_SUCCEEDED: u8 = 0x00;
+const REPLY_GENERAL_FAILURE: u8 = 0x01;
+const REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+#[napi(js_name = "Socks5ProxyListener")]
+pub struct NativeSocks5ProxyListener {
+  connector: ClientConnector,
+}
+
+#[napi]
+impl NativeSocks5ProxyListener {
+  /**
+   * Creates a SOCKS5 proxy that routes every CONNECT request through the given client, like `client.connect()`.
+   * Connections use the client's current stream preferences and default connect timeout,
+   * and are counted in its transfer statistics and reported to its `"stream:connected"` listeners.
+   */
+  #[napi(constructor)]
+  pub fn new(client: &NativeTorClient) -> Self {
+    Self {
+      connector: client.connector(),
+    }
+  }
+
+  /**
+   * Creates a SOCKS5 proxy that routes every CONNECT request through the given client.
+   * Same as `new Socks5ProxyListener(client)`.
+   */
+  #[napi(factory)]
+  pub fn create(client: &NativeTorClient) -> Self {
+    Self::new(client)
+  }
+
+  /**
+   * Starts accepting SOCKS5 connections on `127.0.0.1:port`.
+   * Only the CONNECT command without authentication is supported.
+   * Pass `0` to let the operating system pick a free port, then read it from `Socks5Server.port`.
+   */
+  #[napi]
+  pub async fn listen(&self, port: u16) -> napi::Result<NativeSocks5Server> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+    let port = listener.local_addr()?.port();
+    let cancel_token = CancellationToken::new();
+
+    let connector = self.connector.clone();
+    let token = cancel_token.clone();
+    tokio::spawn(async move {
+      loop {
+        let socket = tokio::select! {
+          biased;
+
+          _ = token.cancelled() => break,
+          accepted = listener.accept() => match accepted {
+            Ok((socket, _)) => socket,
+            Err(_) => continue,
+          },
+        };
+
+        let connector = connector.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+          tokio::select! {
+            biased;
+
+            _ = token.cancelled() => {}
+            _ = handle_connection(&connector, socket) => {}
+          }
+        });
+      }
+    });
+
+    Ok(NativeSocks5Server { cancel_token, port })
+  }
+}
+
+#[napi(js_name = "Socks5Server", custom_finalize)]
+pub struct NativeSocks5Server {
+  cancel_token: CancellationToken,
+  port: u16,
+}
+
+#[napi]
+impl NativeSocks5Server {
+  /**
+   * This class cannot be constructed manually.
+   */
+  #[napi(constructor)]
+  pub fn new() -> napi::Result<Self> {
+    Err(napi::Error::new(
+      napi::Status::GenericFailure,
+      "This class cannot be constructed manually.".to_string(),
+    ))
+  }
+
+  /**
+   * The local port the proxy is listening on.
+   */
+  #[napi(getter)]
+  pub fn port(&self) -> u16 {
+    self.port
+  }
+
+  /**
+   * Stops accepting connections and closes every relayed connection.
+   */
+  #[napi]
+  pub fn close(&self) {
+    self.cancel_token.cancel();
+  }
+}
+
+impl ObjectFinalize for NativeSocks5Server {
+  fn finalize(self, _env: napi::Env) -> napi::Result<()> {
+    self.close();
+    Ok(())
+  }
+}
+
+async fn handle_connection(
+  connector: &ClientConnector,
+  mut socket: TcpStream,
+) -> std::io::Result<()> {
+  let mut header = [0u8; 2];
+  socket.read_exact(&mut header).await?;
+  if header[0] != SOCKS_VERSION {
+    return Ok(());
+  }
+
+  let mut methods = vec![0u8; header[1] as usize];
+  socket.read_exact(&mut methods).await?;
+  if !methods.contains(&METHOD_NO_AUTH) {
+    socket
+      .write_all(&[SOCKS_VERSION, METHOD_NONE_ACCEPTABLE])
+      .await?;
+    return Ok(());
+  }
+  socket.write_all(&[SOCKS_VERSION, METHOD_NO_AUTH]).await?;
+
+  let mut request = [0u8; 4];
+  socket.read_exact(&mut request).await?;
+  if request[1] != CMD_CONNECT {
+    return send_reply(&mut socket, REPLY_COMMAND_NOT_SUPPORTED).await;
+  }
+
+  let host = match request[3] {
+    ATYP_IPV4 => {
+      let mut addr = [0u8; 4];
+      socket.read_exact(&mut addr).await?;
+      Ipv4Addr::from(addr).to_string()
+    }
+    ATYP_DOMAIN => {
+      let mut len = [0u8; 1];
+      socket.read_exact(&mut len).await?;
+      let mut domain = vec![0u8; len[0] as usize];
+      socket.read_exact(&mut domain).await?;
+      String::from_utf8_lossy(&domain).into_owned()
+    }
+    ATYP_IPV6 => {
+      let mut addr = [0u8; 16];
+      socket.read_exact(&mut addr).await?;
+      format!("[{}]", Ipv6Addr::from(addr))
+    }
+    _ => return send_reply(&mut socket, REPLY_ADDRESS_NOT_SUPPORTED).await,
+  };
+
+  let mut port = [0u8; 2];
+  socket.read_exact(&mut port).await?;
+  let address = format!("{}:{}", host, u16::from_be_bytes(port));
+
+  let stream = match connector.connect(&address).await {
+    Ok(stream) => stream,
+    Err(_) => return send_reply(&mut socket, REPLY_GENERAL_FAILURE).await,
+  };
+  send_reply(&mut socket, REPLY_SUCCEEDED).await?;
+
+  let transfer = connector.transfer();
+  let mut socket = InspectReader::new(socket, |bytes: &[u8]| transfer.bytes_sent(bytes.len()));
+  let mut stream = InspectReader::new(stream, |bytes: &[u8]| transfer.bytes_received(bytes.len()));
+  tokio::io::copy_bidirectional(&mut socket, &mut stream).await?;
+  Ok(())
+}
+
+async fn send_reply(socket: &mut TcpStream, reply: u8) -> std::io::Result<()> {
+  socket
+    .write_all(&[SOCKS_VERSION, reply, 0x00, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+    .await
+}
@@ -1,50 +1,350 @@
 use arti_client::DataStream;
+use napi::bindgen_prelude::within_runtime_if_available;
+use napi::bindgen_prelude::BigInt;
 use napi::bindgen_prelude::Buffer;
 use napi::bindgen_prelude::ObjectFinalize;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+  UnknownReturnValue,
+};
 use napi::tokio::io::AsyncReadExt;
 use napi::tokio::io::AsyncWriteExt;
+use napi::tokio::io::BufReader;
+use napi::tokio::io::{AsyncRead, AsyncWrite, ReadBuf, ReadHalf, WriteHalf};
+use napi::tokio::sync::{Mutex, OnceCell};
+use napi::{Env, JsBuffer, JsFunction, JsObject, JsUnknown, ValueType};
 use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
 use rustls::RootCertStore;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
 use tokio_rustls::TlsConnector;
 use tokio_rustls::TlsStream;
+use tokio_util::io::InspectReader;
 use tokio_util::sync::CancellationToken;
+use tor_linkspec::HasRelayIds;
+use tor_proto::stream::ClientStreamCtrl;
 
+use crate::client::TransferCounters;
+use crate::hs_service::ServiceCounters;
+use crate::http::{self, NativeHttpResponse};
 use crate::utils;
 
+type PendingStream = JoinHandle<napi::Result<DataStream>>;
+
+#[allow(clippy::large_enum_variant)]
 enum MaybeTlsStream {
   Plain(DataStream),
   Tls(Box<TlsStream<DataStream>>),
+  /// A connection still being established in the background; `None` once it has failed.
+  Connecting(Option<PendingStream>),
+}
+
+fn not_connected() -> std::io::Error {
+  std::io::Error::new(std::io::ErrorKind::NotConnected, "Stream failed to connect")
 }
 
 impl MaybeTlsStream {
+  /// Waits for a pending connection, turning it into a plain stream.
+  async fn connected(&mut self) -> std::io::Result<()> {
+    if let MaybeTlsStream::Connecting(pending) = self {
+      let handle = pending.take().ok_or_else(not_connected)?;
+      let stream = handle
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(|err| std::io::Error::other(err.reason))?;
+      *self = MaybeTlsStream::Plain(stream);
+    }
+    Ok(())
+  }
+
   async fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+    self.connected().await?;
     match self {
       MaybeTlsStream::Plain(s) => s.write_all(buf).await,
       MaybeTlsStream::Tls(s) => s.write_all(buf).await,
+      MaybeTlsStream::Connecting(_) => Err(not_connected()),
     }
   }
 
   async fn flush(&mut self) -> std::io::Result<()> {
+    self.connected().await?;
     match self {
       MaybeTlsStream::Plain(s) => s.flush().await,
       MaybeTlsStream::Tls(s) => s.flush().await,
+      MaybeTlsStream::Connecting(_) => Err(not_connected()),
     }
   }
 
   async fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    self.connected().await?;
     match self {
       MaybeTlsStream::Plain(s) => s.read(buf).await,
       MaybeTlsStream::Tls(s) => s.read(buf).await,
+      MaybeTlsStream::Connecting(_) => Err(not_connected()),
+    }
+  }
+
+  async fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    self.connected().await?;
+    match self {
+      MaybeTlsStream::Plain(s) => s.read_exact(buf).await,
+      MaybeTlsStream::Tls(s) => s.read_exact(buf).await,
+      MaybeTlsStream::Connecting(_) => Err(not_connected()),
+    }
+  }
+}
+
+impl AsyncRead for MaybeTlsStream {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<std::io::Result<()>> {
+    match self.get_mut() {
+      MaybeTlsStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+      MaybeTlsStream::Tls(s) => Pin::new(s).poll_read(cx, buf),
+      MaybeTlsStream::Connecting(_) => Poll::Ready(Err(not_connected())),
+    }
+  }
+}
+
+impl AsyncWrite for MaybeTlsStream {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &[u8],
+  ) -> Poll<std::io::Result<usize>> {
+    match self.get_mut() {
+      MaybeTlsStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+      MaybeTlsStream::Tls(s) => Pin::new(s).poll_write(cx, buf),
+      MaybeTlsStream::Connecting(_) => Poll::Ready(Err(not_connected())),
+    }
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+    match self.get_mut() {
+      MaybeTlsStream::Plain(s) => Pin::new(s).poll_flush(cx),
+      MaybeTlsStream::Tls(s) => Pin::new(s).poll_flush(cx),
+      MaybeTlsStream::Connecting(_) => Poll::Ready(Err(not_connected())),
+    }
+  }
+
+  fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+    match self.get_mut() {
+      MaybeTlsStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+      MaybeTlsStream::Tls(s) => Pin::new(s).poll_shutdown(cx),
+      MaybeTlsStream::Connecting(_) => Poll::Ready(Err(not_connected())),
+    }
+  }
+}
+
+/// A stream with a buffer of bytes returned by `peek()`, which every read drains first.
+struct PeekableStream {
+  inner: MaybeTlsStream,
+  peeked: VecDeque<u8>,
+}
+
+impl PeekableStream {
+  fn new(inner: MaybeTlsStream) -> Self {
+    Self {
+      inner,
+      peeked: VecDeque::new(),
+    }
+  }
+
+  async fn connected(&mut self) -> std::io::Result<()> {
+    self.inner.connected().await
+  }
+
+  async fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+    self.inner.write_all(buf).await
+  }
+
+  async fn flush(&mut self) -> std::io::Result<()> {
+    self.inner.flush().await
+  }
+
+  /// Copies buffered bytes into `buf`, returning how many were copied.
+  fn take_peeked(&mut self, buf: &mut [u8]) -> usize {
+    let n = buf.len().min(self.peeked.len());
+    for (dst, src) in buf.iter_mut().zip(self.peeked.drain(..n)) {
+      *dst = src;
+    }
+    n
+  }
+
+  async fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    if !self.peeked.is_empty() {
+      return Ok(self.take_peeked(buf));
+    }
+    self.inner.read(buf).await
+  }
+
+  async fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let n = self.take_peeked(buf);
+    self.inner.read_exact(&mut buf[n..]).await?;
+    Ok(buf.len())
+  }
+
+  /// Returns up to `len` bytes without consuming them, reading once more from the stream if fewer are buffered.
+  async fn peek(&mut self, len: usize) -> std::io::Result<Vec<u8>> {
+    if self.peeked.len() < len {
+      let mut buf = vec![0u8; len - self.peeked.len()];
+      let n = self.inner.read(&mut buf).await?;
+      self.peeked.extend(&buf[..n]);
+    }
+    Ok(self.peeked.iter().take(len).copied().collect())
+  }
+}
+
+impl AsyncRead for PeekableStream {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<std::io::Result<()>> {
+    let this = self.get_mut();
+    if this.peeked.is_empty() {
+      return Pin::new(&mut this.inner).poll_read(cx, buf);
+    }
+
+    let n = buf.remaining().min(this.peeked.len());
+    buf.put_slice(&this.peeked.make_contiguous()[..n]);
+    this.peeked.drain(..n);
+    Poll::Ready(Ok(()))
+  }
+}
+
+impl AsyncWrite for PeekableStream {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &[u8],
+  ) -> Poll<std::io::Result<usize>> {
+    Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+    Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+  }
+
+  fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+    Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+  }
+}
+
+/// Counters a stream reports its traffic to: those of the service that accepted it, or of the client that opened it.
+#[derive(Clone, Default)]
+struct StreamCounters {
+  service: Option<Arc<ServiceCounters>>,
+  client: Option<Arc<TransferCounters>>,
+}
+
+/// Cancels a `Duplex`'s background tasks and releases its service connection once, when the duplex is destroyed
+/// or, if it never is, when it is garbage-collected.
+struct DuplexGuard {
+  token: CancellationToken,
+  service: std::sync::Mutex<Option<Arc<ServiceCounters>>>,
+}
+
+impl DuplexGuard {
+  fn new(token: CancellationToken, service: Option<Arc<ServiceCounters>>) -> Self {
+    Self {
+      token,
+      service: std::sync::Mutex::new(service),
+    }
+  }
+
+  fn close(&self) {
+    self.token.cancel();
+    if let Some(counters) = self.service.lock().unwrap().take() {
+      counters.connection_closed();
+    }
+  }
+}
+
+impl Drop for DuplexGuard {
+  fn drop(&mut self) {
+    self.close();
+  }
+}
+
+type DuplexHalves = (
+  Mutex<ReadHalf<PeekableStream>>,
+  Mutex<WriteHalf<PeekableStream>>,
+);
+
+/// I/O side of a stream handed over to a Node.js `Duplex`, split so reads and writes can run concurrently.
+struct DuplexIo {
+  stream: std::sync::Mutex<Option<PeekableStream>>,
+  halves: OnceCell<DuplexHalves>,
+  counters: StreamCounters,
+}
+
+impl DuplexIo {
+  fn new(stream: PeekableStream, counters: StreamCounters) -> Self {
+    Self {
+      stream: std::sync::Mutex::new(Some(stream)),
+      halves: OnceCell::new(),
+      counters,
     }
   }
+
+  /// Waits for a pending connection on first use, then splits the stream.
+  async fn halves(&self) -> std::io::Result<&DuplexHalves> {
+    self
+      .halves
+      .get_or_try_init(|| async {
+        let mut stream = self
+          .stream
+          .lock()
+          .unwrap()
+          .take()
+          .ok_or_else(not_connected)?;
+        stream.connected().await?;
+        let (reader, writer) = tokio::io::split(stream);
+        Ok((Mutex::new(reader), Mutex::new(writer)))
+      })
+      .await
+  }
+
+  async fn read(&self, len: usize) -> std::io::Result<Vec<u8>> {
+    let mut reader = self.halves().await?.0.lock().await;
+    let mut buf = vec![0u8; len];
+    let n = reader.read(&mut buf).await?;
+    record_received(&self.counters, n);
+    buf.truncate(n);
+    Ok(buf)
+  }
+
+  async fn write(&self, buf: &[u8]) -> std::io::Result<()> {
+    let mut writer = self.halves().await?.1.lock().await;
+    writer.write_all(buf).await?;
+    record_sent(&self.counters, buf.len());
+    // Node.js streams have no explicit flush, so every write is sent right away.
+    writer.flush().await
+  }
+
+  async fn shutdown(&self) -> std::io::Result<()> {
+    self.halves().await?.1.lock().await.shutdown().await
+  }
 }
 
 #[napi(js_name = "TorStream", custom_finalize)]
 pub struct NativeTorStream {
-  stream: Option<MaybeTlsStream>,
+  stream: Option<PeekableStream>,
   cancel_token: CancellationToken,
+  nodelay: bool,
+  counters: StreamCounters,
+  connected_at_ms: Option<f64>,
+  establish_ms: Option<u32>,
 }
 
 #[napi]
@@ -60,13 +360,64 @@ impl NativeTorStream {
     ))
   }
 
-  pub fn from_stream(stream: DataStream) -> Self {
+  fn from_stream(stream: DataStream, counters: StreamCounters, establish_ms: Option<u32>) -> Self {
+    Self {
+      stream: Some(PeekableStream::new(MaybeTlsStream::Plain(stream))),
+      cancel_token: CancellationToken::new(),
+      nodelay: false,
+      counters,
+      connected_at_ms: Some(unix_time_ms()),
+      establish_ms,
+    }
+  }
+
+  /// Wraps a stream opened by a connection attempt launched at `started`.
+  pub fn from_client_stream(
+    stream: DataStream,
+    transfer: Arc<TransferCounters>,
+    started: Instant,
+  ) -> Self {
+    let establish_ms = started.elapsed().as_millis().min(u32::MAX as u128) as u32;
+
+    Self::from_stream(
+      stream,
+      StreamCounters {
+        service: None,
+        client: Some(transfer),
+      },
+      Some(establish_ms),
+    )
+  }
+
+  pub fn from_pending_stream(pending: PendingStream, transfer: Arc<TransferCounters>) -> Self {
     Self {
-      stream: Some(MaybeTlsStream::Plain(stream)),
+      stream: Some(PeekableStream::new(MaybeTlsStream::Connecting(Some(
+        pending,
+      )))),
       cancel_token: CancellationToken::new(),
+      nodelay: false,
+      counters: StreamCounters {
+        service: None,
+        client: Some(transfer),
+      },
+      connected_at_ms: None,
+      establish_ms: None,
     }
   }
 
+  pub fn from_service_stream(stream: DataStream, counters: Arc<ServiceCounters>) -> Self {
+    counters.connection_opened();
+
+    Self::from_stream(
+      stream,
+      StreamCounters {
+        service: Some(counters),
+        client: None,
+      },
+      None,
+    )
+  }
+
   /**
    * Upgrade the stream to use TLS.
    *
@@ -80,10 +431,26 @@ impl NativeTorStream {
    */
   #[napi]
   pub async unsafe fn enable_tls(&mut self, domain: String) -> napi::Result<()> {
-    let plain = match self.stream.take() {
+    if let Some(stream) = &mut self.stream {
+      utils::map_error(stream.connected().await)?;
+    }
+
+    if self
+      .stream
+      .as_ref()
+      .is_some_and(|stream| !stream.peeked.is_empty())
+    {
+      return Err(napi::Error::from_reason(
+        "Cannot enable TLS while peeked bytes are unread",
+      ));
+    }
+
+    let plain = match self.stream.take().map(|stream| stream.inner) {
       Some(MaybeTlsStream::Plain(s)) => s,
       Some(MaybeTlsStream::Tls(_)) => return Err(napi::Error::from_reason("TLS already enabled")),
-      None => return Err(napi::Error::from_reason("Stream closed")),
+      Some(MaybeTlsStream::Connecting(_)) | None => {
+        return Err(napi::Error::from_reason("Stream closed"))
+      }
     };
 
     let mut root_cert_store = RootCertStore::empty();
@@ -97,7 +464,7 @@ impl NativeTorStream {
     let stream = connector.connect(dnsname, plain).await?;
     let stream = TlsStream::Client(stream);
 
-    self.stream = Some(MaybeTlsStream::Tls(Box::new(stream)));
+    self.stream = Some(PeekableStream::new(MaybeTlsStream::Tls(Box::new(stream))));
     Ok(())
   }
 
@@ -108,13 +475,40 @@ impl NativeTorStream {
    */
   #[napi]
   pub async unsafe fn wait_for_connection(&mut self) -> napi::Result<()> {
-    if let Some(MaybeTlsStream::Plain(stream)) = &mut self.stream {
-      utils::map_error(stream.wait_for_connection().await)
+    if let Some(stream) = &mut self.stream {
+      utils::map_error(stream.connected().await)?;
+    }
+
+    if let Some(MaybeTlsStream::Plain(stream)) =
+      self.stream.as_mut().map(|stream| &mut stream.inner)
+    {
+      utils::map_error(stream.wait_for_connection().await)?;
+      self.connected_at_ms.get_or_insert_with(unix_time_ms);
+      Ok(())
     } else {
       Err(napi::Error::from_reason("Stream was closed"))
     }
   }
 
+  /**
+   * When the stream was connected, in milliseconds since the Unix epoch.
+   * Streams returned by `TorClient.connectOptimistic()` report `null` until `waitForConnection()` has succeeded.
+   */
+  #[napi(getter)]
+  pub fn connected_at_ms(&self) -> Option<f64> {
+    self.connected_at_ms
+  }
+
+  /**
+   * Milliseconds the `TorClient` connect method took to establish this stream, including building the circuit.
+   * For `connectWithRetry()`, only the successful attempt is counted.
+   * `null` for streams returned by `TorClient.connectOptimistic()` and for streams accepted by an onion service.
+   */
+  #[napi(getter)]
+  pub fn establish_timeout_ms(&self) -> Option<u32> {
+    self.establish_ms
+  }
+
   /**
    * Attempts to write an entire buffer into this writer.
    * @example
@@ -131,11 +525,7 @@ impl NativeTorStream {
    */
   #[napi]
   pub async unsafe fn write(&mut self, src: Buffer) -> napi::Result<()> {
-    if let Some(stream) = &mut self.stream {
-      utils::map_error(stream.write_all(&src).await)
-    } else {
-      Err(napi::Error::from_reason("Stream was closed"))
-    }
+    self.write_bytes(&src).await
   }
 
   /**
@@ -161,6 +551,7 @@ impl NativeTorStream {
       if let Some(stream) = &mut self.stream {
         let mut buf = vec![0u8; len as usize];
         let n = utils::map_error(stream.read(&mut buf).await)?;
+        record_received(&self.counters, n);
         buf.truncate(n);
         Ok(Buffer::from(buf))
       } else {
@@ -180,13 +571,1059 @@ impl NativeTorStream {
   }
 
   /**
-   * Close the stream.
+   * Returns up to `len` incoming bytes without consuming them: the next reads return the same bytes again.
+   * Waits for data if nothing is buffered yet. If fewer than `len` bytes are buffered, it reads from the stream
+   * once more, so call it again to wait for more bytes. An empty buffer means the stream reached EOF.
+   *
+   * Useful to detect the protocol spoken on a stream, e.g. a TLS handshake, before handing it over.
    */
   #[napi]
-  pub unsafe fn close(&mut self) {
-    self.stream.take();
-    self.cancel_token.cancel();
+  pub async unsafe fn peek(&mut self, len: u32) -> napi::Result<Buffer> {
+    let token = self.cancel_token.clone();
+
+    let peek_fut = async {
+      if let Some(stream) = &mut self.stream {
+        Ok(Buffer::from(utils::map_error(
+          stream.peek(len as usize).await,
+        )?))
+      } else {
+        Err(napi::Error::from_reason("Stream was closed"))
+      }
+    };
+
+    tokio::select! {
+      biased;
+
+      _ = token.cancelled() => {
+        Err(napi::Error::from_reason("Stream was closed during read"))
+      }
+
+      result = peek_fut => result
+    }
+  }
+
+  /**
+   * Reads exactly `n` bytes, failing if they do not all arrive within `timeoutMs` milliseconds.
+   *
+   * @throws If the timeout expires or the stream ends first; the message includes how many bytes were received.
+   */
+  #[napi]
+  pub async unsafe fn read_exact_timeout(
+    &mut self,
+    n: u32,
+    timeout_ms: u32,
+  ) -> napi::Result<Buffer> {
+    let token = self.cancel_token.clone();
+    let len = n as usize;
+    let mut buf = vec![0u8; len];
+    let mut received = 0;
+
+    let read_fut = async {
+      let Some(stream) = &mut self.stream else {
+        return Err(napi::Error::from_reason("Stream was closed"));
+      };
+
+      while received < len {
+        let read = utils::map_error(stream.read(&mut buf[received..]).await)?;
+        record_received(&self.counters, read);
+        if read == 0 {
+          return Err(napi::Error::from_reason(format!(
+            "Stream ended after receiving {received} of {len} bytes"
+          )));
+        }
+        received += read;
+      }
+      Ok(())
+    };
+
+    let result = tokio::select! {
+      biased;
+
+      _ = token.cancelled() => None,
+      result = tokio::time::timeout(Duration::from_millis(timeout_ms as u64), read_fut) => Some(result),
+    };
+
+    match result {
+      None => Err(napi::Error::from_reason("Stream was closed during read")),
+      Some(Err(_)) => Err(napi::Error::from_reason(format!(
+        "Timed out after receiving {received} of {len} bytes"
+      ))),
+      Some(Ok(result)) => result.map(|_| Buffer::from(buf)),
+    }
+  }
+
+  /**
+   * Reads from the stream until EOF and returns everything that was received.
+   *
+   * @param maxBytes - Optional limit; exceeding it rejects with `"Response too large"`.
+   */
+  #[napi]
+  pub async unsafe fn read_all(&mut self, max_bytes: Option<u32>) -> napi::Result<Buffer> {
+    let token = self.cancel_token.clone();
+
+    let read_fut = async {
+      let mut data = Vec::new();
+
+      if let Some(stream) = &mut self.stream {
+        let mut buf = vec![0u8; 8192];
+
+        loop {
+          let n = utils::map_error(stream.read(&mut buf).await)?;
+          record_received(&self.counters, n);
+          if n == 0 {
+            break;
+          }
+
+          data.extend_from_slice(&buf[..n]);
+          if max_bytes.is_some_and(|max| data.len() > max as usize) {
+            return Err(napi::Error::from_reason("Response too large"));
+          }
+        }
+      }
+
+      Ok(Buffer::from(data))
+    };
+
+    tokio::select! {
+      biased;
+
+      _ = token.cancelled() => {
+        Err(napi::Error::from_reason("Stream was closed during read"))
+      }
+
+      result = read_fut => result
+    }
+  }
+
+  /**
+   * Waits until the peer closes the stream and returns everything that was received, like `readAll()`.
+   * Use it when the end of the data is only marked by the connection closing, e.g. HTTP/1.0 responses
+   * without `Content-Length`. Rejects if the stream ends with an error instead of a clean close.
+   *
+   * @param maxBytes - Optional limit; exceeding it rejects with `"Response too large"`.
+   */
+  #[napi]
+  pub async unsafe fn read_until_closed(&mut self, max_bytes: Option<u32>) -> napi::Result<Buffer> {
+    self.read_all(max_bytes).await
+  }
+
+  /**
+   * Reads the stream until EOF, passing each chunk to `onChunk` instead of accumulating it in memory.
+   * Memory-efficient alternative to `readAll()` for large downloads.
+   * Each callback invocation is awaited before reading further, so a slow consumer applies backpressure.
+   * Returns the total number of bytes read.
+   *
+   * @param onChunk - Called with every chunk read from the stream.
+   * @param maxBytes - Optional limit; exceeding it rejects with `"Response too large"`.
+   */
+  #[napi(ts_args_type = "onChunk: (chunk: Buffer) => any, maxBytes?: number | undefined | null")]
+  pub async unsafe fn read_all_streaming(
+    &mut self,
+    on_chunk: ThreadsafeFunction<(Buffer,), ErrorStrategy::Fatal>,
+    max_bytes: Option<i64>,
+  ) -> napi::Result<u64> {
+    let token = self.cancel_token.clone();
+
+    let read_fut = async {
+      let mut total: u64 = 0;
+
+      if let Some(stream) = &mut self.stream {
+        let mut buf = vec![0u8; 64 * 1024];
+
+        loop {
+          let n = utils::map_error(stream.read(&mut buf).await)?;
+          record_received(&self.counters, n);
+          if n == 0 {
+            break;
+          }
+
+          total += n as u64;
+          if max_bytes.is_some_and(|max| total > max.max(0) as u64) {
+            return Err(napi::Error::from_reason("Response too large"));
+          }
+
+          on_chunk
+            .call_async::<UnknownReturnValue>((Buffer::from(&buf[..n]),))
+            .await?;
+        }
+      }
+
+      Ok(total)
+    };
+
+    tokio::select! {
+      biased;
+
+      _ = token.cancelled() => {
+        Err(napi::Error::from_reason("Stream was closed during read"))
+      }
+
+      result = read_fut => result
+    }
+  }
+
+  /**
+   * Copies data from this stream into `dest` until this stream reaches EOF, then flushes `dest`.
+   * If either stream is closed during the copy, it stops and returns the bytes copied so far.
+   * Returns the total number of bytes copied.
+   *
+   * @param dest - The stream to write to.
+   * @param bufferSize - Size of the intermediate buffer, 8192 bytes by default.
+   */
+  #[napi]
+  pub async unsafe fn copy_to(
+    &mut self,
+    dest: &mut NativeTorStream,
+    buffer_size: Option<u32>,
+  ) -> napi::Result<u64> {
+    if std::ptr::eq(self, dest) {
+      return Err(napi::Error::from_reason("Cannot copy a stream into itself"));
+    }
+
+    let source_token = self.cancel_token.clone();
+    let dest_token = dest.cancel_token.clone();
+    let mut total: u64 = 0;
+
+    let copy_fut = async {
+      let stream = match &mut self.stream {
+        Some(stream) => stream,
+        None => return Err(napi::Error::from_reason("Stream was closed")),
+      };
+      let mut buf = vec![0u8; buffer_size.unwrap_or(8192).max(1) as usize];
+
+      loop {
+        let n = utils::map_error(stream.read(&mut buf).await)?;
+        record_received(&self.counters, n);
+        if n == 0 {
+          break;
+        }
+
+        dest.write_bytes(&buf[..n]).await?;
+        total += n as u64;
+      }
+
+      match &mut dest.stream {
+        Some(dest_stream) => utils::map_error(dest_stream.flush().await),
+        None => Err(napi::Error::from_reason("Stream was closed")),
+      }
+    };
+
+    let result = tokio::select! {
+      biased;
+
+      _ = source_token.cancelled() => Ok(()),
+      _ = dest_token.cancelled() => Ok(()),
+      result = copy_fut => result,
+    };
+
+    result.map(|_| total)
   }
+
+  /**
+   * Reads a variable-length integer encoded with the protobuf LEB128 scheme.
+   * Values up to 64 bits are supported; longer encodings are rejected.
+   */
+  #[napi]
+  pub async unsafe fn read_varint(&mut self) -> napi::Result<BigInt> {
+    let token = self.cancel_token.clone();
+
+    let read_fut = async {
+      if let Some(stream) = &mut self.stream {
+        let mut value: u64 = 0;
+        let mut byte = [0u8; 1];
+
+        for shift in (0..64).step_by(7) {
+          utils::map_error(stream.read_exact(&mut byte).await)?;
+          record_received(&self.counters, 1);
+          let bits = (byte[0] & 0x7f) as u64;
+
+          if shift == 63 && bits > 1 {
+            return Err(napi::Error::from_reason("Varint overflows 64 bits"));
+          }
+
+          value |= bits << shift;
+
+          if byte[0] & 0x80 == 0 {
+            return Ok(BigInt::from(value));
+          }
+        }
+
+        Err(napi::Error::from_reason("Varint overflows 64 bits"))
+      } else {
+        Err(napi::Error::from_reason("Stream was closed"))
+      }
+    };
+
+    tokio::select! {
+      biased;
+
+      _ = token.cancelled() => {
+        Err(napi::Error::from_reason("Stream was closed during read"))
+      }
+
+      result = read_fut => result
+    }
+  }
+
+  /**
+   * Encodes a non-negative integer of up to 64 bits with the protobuf LEB128 scheme and writes it to the stream.
+   */
+  #[napi]
+  pub async unsafe fn write_varint(&mut self, value: BigInt) -> napi::Result<()> {
+    let (signed, mut value, lossless) = value.get_u64();
+
+    if signed || !lossless {
+      return Err(napi::Error::from_reason(
+        "Varint value must be an unsigned 64-bit integer",
+      ));
+    }
+
+    let mut buf = Vec::with_capacity(10);
+    loop {
+      let byte = (value & 0x7f) as u8;
+      value >>= 7;
+      if value == 0 {
+        buf.push(byte);
+        break;
+      }
+      buf.push(byte | 0x80);
+    }
+
+    self.write_bytes(&buf).await
+  }
+
+  /**
+   * Reads a single byte from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u8(&mut self) -> napi::Result<u8> {
+    Ok(self.read_array::<1>().await?[0])
+  }
+
+  /**
+   * Reads an unsigned 16-bit integer in big-endian byte order from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u16_be(&mut self) -> napi::Result<u16> {
+    Ok(u16::from_be_bytes(self.read_array().await?))
+  }
+
+  /**
+   * Reads an unsigned 16-bit integer in little-endian byte order from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u16_le(&mut self) -> napi::Result<u16> {
+    Ok(u16::from_le_bytes(self.read_array().await?))
+  }
+
+  /**
+   * Reads an unsigned 32-bit integer in big-endian byte order from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u32_be(&mut self) -> napi::Result<u32> {
+    Ok(u32::from_be_bytes(self.read_array().await?))
+  }
+
+  /**
+   * Reads an unsigned 32-bit integer in little-endian byte order from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u32_le(&mut self) -> napi::Result<u32> {
+    Ok(u32::from_le_bytes(self.read_array().await?))
+  }
+
+  /**
+   * Reads an unsigned 64-bit integer in big-endian byte order from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u64_be(&mut self) -> napi::Result<BigInt> {
+    Ok(BigInt::from(u64::from_be_bytes(self.read_array().await?)))
+  }
+
+  /**
+   * Reads an unsigned 64-bit integer in little-endian byte order from the stream.
+   *
+   * @throws If the stream ends first.
+   */
+  #[napi]
+  pub async unsafe fn read_u64_le(&mut self) -> napi::Result<BigInt> {
+    Ok(BigInt::from(u64::from_le_bytes(self.read_array().await?)))
+  }
+
+  /**
+   * Writes a single byte to the stream.
+   */
+  #[napi]
+  pub async unsafe fn write_u8(&mut self, value: u8) -> napi::Result<()> {
+    self.write_bytes(&[value]).await
+  }
+
+  /**
+   * Writes an unsigned 16-bit integer to the stream in big-endian byte order.
+   */
+  #[napi]
+  pub async unsafe fn write_u16_be(&mut self, value: u16) -> napi::Result<()> {
+    self.write_bytes(&value.to_be_bytes()).await
+  }
+
+  /**
+   * Writes an unsigned 16-bit integer to the stream in little-endian byte order.
+   */
+  #[napi]
+  pub async unsafe fn write_u16_le(&mut self, value: u16) -> napi::Result<()> {
+    self.write_bytes(&value.to_le_bytes()).await
+  }
+
+  /**
+   * Writes an unsigned 32-bit integer to the stream in big-endian byte order.
+   */
+  #[napi]
+  pub async unsafe fn write_u32_be(&mut self, value: u32) -> napi::Result<()> {
+    self.write_bytes(&value.to_be_bytes()).await
+  }
+
+  /**
+   * Writes an unsigned 32-bit integer to the stream in little-endian byte order.
+   */
+  #[napi]
+  pub async unsafe fn write_u32_le(&mut self, value: u32) -> napi::Result<()> {
+    self.write_bytes(&value.to_le_bytes()).await
+  }
+
+  /**
+   * Writes an unsigned 64-bit integer to the stream in big-endian byte order.
+   */
+  #[napi]
+  pub async unsafe fn write_u64_be(&mut self, value: BigInt) -> napi::Result<()> {
+    self.write_bytes(&bigint_to_u64(value)?.to_be_bytes()).await
+  }
+
+  /**
+   * Writes an unsigned 64-bit integer to the stream in little-endian byte order.
+   */
+  #[napi]
+  pub async unsafe fn write_u64_le(&mut self, value: BigInt) -> napi::Result<()> {
+    self.write_bytes(&bigint_to_u64(value)?.to_le_bytes()).await
+  }
+
+  /**
+   * Formats a complete HTTP/1.1 request, writes it to the stream and flushes it.
+   * `Content-Length` is set from the body length and `Connection: close` is always sent.
+   * `Host`, `Content-Length` and `Connection` entries in `headers` are ignored.
+   *
+   * @param method - Request method, e.g. `"GET"` or `"POST"`.
+   * @param path - Request target, e.g. `"/index.html"`.
+   * @param host - Value of the `Host` header, e.g. `"httpbin.org"`.
+   * @param headers - Additional headers as `[name, value]` pairs.
+   * @param body - Optional request body.
+   *
+   * @example
+   * ```ts
+   * const stream = await client.connect("httpbin.org:80");
+   * await stream.writeHttpRequest("GET", "/ip", "httpbin.org", [["Accept", "application/json"]]);
+   * ```
+   */
+  #[napi]
+  pub async unsafe fn write_http_request(
+    &mut self,
+    method: String,
+    path: String,
+    host: String,
+    headers: Option<Vec<Vec<String>>>,
+    body: Option<Buffer>,
+  ) -> napi::Result<()> {
+    let is_invalid = |value: &str| value.is_empty() || value.contains(['\r', '\n']);
+    if is_invalid(&method) || method.contains(' ') || is_invalid(&path) || path.contains(' ') {
+      return Err(napi::Error::from_reason("Invalid HTTP request line"));
+    }
+    if is_invalid(&host) {
+      return Err(napi::Error::from_reason("Invalid HTTP host"));
+    }
+
+    let body = body.map(|body| body.to_vec()).unwrap_or_default();
+    let mut request = format!("{method} {path} HTTP/1.1\r\nHost: {host}\r\n");
+
+    for header in headers.unwrap_or_default() {
+      let [name, value] = header.as_slice() else {
+        return Err(napi::Error::from_reason(
+          "HTTP headers must be [name, value] pairs",
+        ));
+      };
+
+      if is_invalid(name) || name.contains(':') || value.contains(['\r', '\n']) {
+        return Err(napi::Error::from_reason(format!(
+          "Invalid HTTP header: {name}"
+        )));
+      }
+
+      if ["host", "content-length", "connection"].contains(&name.to_ascii_lowercase().as_str()) {
+        continue;
+      }
+
+      request.push_str(&format!("{name}: {value}\r\n"));
+    }
+
+    request.push_str(&format!(
+      "Content-Length: {}\r\nConnection: close\r\n\r\n",
+      body.len()
+    ));
+
+    let mut request = request.into_bytes();
+    request.extend_from_slice(&body);
+
+    self.write_bytes(&request).await?;
+    match &mut self.stream {
+      Some(stream) => utils::map_error(stream.flush().await),
+      None => Err(napi::Error::from_reason("Stream was closed")),
+    }
+  }
+
+  /**
+   * Copies data in both directions between this stream and `other` at the same time.
+   * When one side reaches EOF, the other side's write half is shut down and the copy continues
+   * until both directions are done. If either stream is closed, it stops early.
+   * Returns the number of bytes copied `[thisToOther, otherToThis]`.
+   *
+   * @param other - The stream to relay data with.
+   */
+  #[napi(ts_return_type = "Promise<[bigint, bigint]>")]
+  pub async unsafe fn copy_bidirectional(
+    &mut self,
+    other: &mut NativeTorStream,
+  ) -> napi::Result<Vec<u64>> {
+    if std::ptr::eq(self, other) {
+      return Err(napi::Error::from_reason("Cannot copy a stream into itself"));
+    }
+
+    let self_token = self.cancel_token.clone();
+    let other_token = other.cancel_token.clone();
+    let self_to_other = AtomicU64::new(0);
+    let other_to_self = AtomicU64::new(0);
+
+    let copy_fut = async {
+      let (Some(stream), Some(other_stream)) = (&mut self.stream, &mut other.stream) else {
+        return Err(napi::Error::from_reason("Stream was closed"));
+      };
+      utils::map_error(stream.connected().await)?;
+      utils::map_error(other_stream.connected().await)?;
+
+      let mut stream = InspectReader::new(stream, |bytes: &[u8]| {
+        self_to_other.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        record_received(&self.counters, bytes.len());
+        record_sent(&other.counters, bytes.len());
+      });
+      let mut other_stream = InspectReader::new(other_stream, |bytes: &[u8]| {
+        other_to_self.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        record_received(&other.counters, bytes.len());
+        record_sent(&self.counters, bytes.len());
+      });
+
+      utils::map_error(tokio::io::copy_bidirectional(&mut stream, &mut other_stream).await)
+    };
+
+    let result = tokio::select! {
+      biased;
+
+      _ = self_token.cancelled() => Ok(()),
+      _ = other_token.cancelled() => Ok(()),
+      result = copy_fut => result.map(|_| ()),
+    };
+
+    result.map(|_| {
+      vec![
+        self_to_other.load(Ordering::Relaxed),
+        other_to_self.load(Ordering::Relaxed),
+      ]
+    })
+  }
+
+  /**
+   * Opens a TCP connection to `host:port` and relays data in both directions between it and this stream,
+   * like `copyBidirectional()`, until both sides are done or this stream is closed.
+   * Typically used by an onion service to forward accepted streams to a local server.
+   * Returns the number of bytes relayed `[toTarget, fromTarget]`.
+   *
+   * @param host - Host of the TCP server, e.g. `"127.0.0.1"`.
+   * @param port - Port of the TCP server.
+   */
+  #[napi(ts_return_type = "Promise<[bigint, bigint]>")]
+  pub async unsafe fn proxy_to(&mut self, host: String, port: u16) -> napi::Result<Vec<u64>> {
+    let token = self.cancel_token.clone();
+    let to_target = AtomicU64::new(0);
+    let from_target = AtomicU64::new(0);
+
+    let copy_fut = async {
+      let Some(stream) = &mut self.stream else {
+        return Err(napi::Error::from_reason("Stream was closed"));
+      };
+      utils::map_error(stream.connected().await)?;
+      let target = utils::map_error(TcpStream::connect((host.as_str(), port)).await)?;
+
+      let mut stream = InspectReader::new(stream, |bytes: &[u8]| {
+        to_target.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        record_received(&self.counters, bytes.len());
+      });
+      let mut target = InspectReader::new(target, |bytes: &[u8]| {
+        from_target.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        record_sent(&self.counters, bytes.len());
+      });
+
+      utils::map_error(tokio::io::copy_bidirectional(&mut stream, &mut target).await)
+    };
+
+    let result = tokio::select! {
+      biased;
+
+      _ = token.cancelled() => Ok(()),
+      result = copy_fut => result.map(|_| ()),
+    };
+
+    result.map(|_| {
+      vec![
+        to_target.load(Ordering::Relaxed),
+        from_target.load(Ordering::Relaxed),
+      ]
+    })
+  }
+
+  /**
+   * Reads an HTTP/1.1 response from the stream: the status line, the headers and the body.
+   * The body is decoded from `Transfer-Encoding: chunked`, or delimited by `Content-Length`,
+   * or read until EOF when neither is present. Bytes received after the response are discarded.
+   *
+   * @throws If the response is not valid HTTP/1.x, or its body is larger than 64 MiB.
+   *
+   * @example
+   * ```ts
+   * await stream.writeHttpRequest("GET", "/ip", "httpbin.org");
+   * const response = await stream.readHttpResponse();
+   * console.log(response.status, response.body.toString());
+   * ```
+   */
+  #[napi]
+  pub async unsafe fn read_http_response(&mut self) -> napi::Result<NativeHttpResponse> {
+    let token = self.cancel_token.clone();
+
+    let counters = self.counters.clone();
+    let record = |bytes: &[u8]| record_received(&counters, bytes.len());
+
+    let read_fut = async {
+      if let Some(stream) = &mut self.stream {
+        utils::map_error(stream.connected().await)?;
+      }
+
+      match &mut self.stream {
+        Some(stream) => {
+          http::read_response(&mut BufReader::new(InspectReader::new(stream, record))).await
+        }
+        None => Err(napi::Error::from_reason("Stream was closed")),
+      }
+    };
+
+    tokio::select! {
+      biased;
+
+      _ = token.cancelled() => {
+        Err(napi::Error::from_reason("Stream was closed during read"))
+      }
+
+      result = read_fut => result
+    }
+  }
+
+  /**
+   * Controls whether written data is sent immediately.
+   * Tor streams do not use Nagle's algorithm, but bytes are buffered into relay cells until the stream is flushed.
+   * When enabled, every write is flushed right away, trading bandwidth efficiency for latency.
+   * Applies to both plain and TLS streams.
+   */
+  #[napi]
+  pub fn set_nodelay(&mut self, enabled: bool) -> napi::Result<()> {
+    if self.stream.is_none() {
+      return Err(napi::Error::from_reason("Stream was closed"));
+    }
+
+    self.nodelay = enabled;
+    Ok(())
+  }
+
+  /**
+   * Returns the number of hops in the circuit carrying this stream, as seen from this side.
+   * For onion service connections this is only the local half of the rendezvous path, counting
+   * the virtual hop to the other party; the hops chosen by the other side are not visible, so the full
+   * rendezvous path cannot be reported. Returns `null` when the circuit cannot be inspected, e.g. while
+   * `connectOptimistic()` is still connecting or after the circuit has closed.
+   *
+   * This is informational only and must not be used for security decisions.
+   */
+  #[napi]
+  pub fn local_circuit_hops(&self) -> napi::Result<Option<u32>> {
+    let data_stream = match self.stream.as_ref().map(|stream| &stream.inner) {
+      Some(MaybeTlsStream::Plain(stream)) => Some(stream),
+      Some(MaybeTlsStream::Tls(stream)) => Some(stream.get_ref().0),
+      Some(MaybeTlsStream::Connecting(_)) => None,
+      None => return Err(napi::Error::from_reason("Stream was closed")),
+    };
+
+    Ok(
+      data_stream
+        .and_then(|stream| stream.client_stream_ctrl())
+        .and_then(|ctrl| ctrl.circuit())
+        .and_then(|circuit| circuit.n_hops().ok())
+        .map(|hops| hops as u32),
+    )
+  }
+
+  /**
+   * Returns the RSA fingerprints of the relays in the circuit carrying this stream, from the entry to the exit,
+   * as 40-character uppercase hex strings. Virtual hops, such as the last hop of an onion service circuit, are skipped.
+   * Returns an empty array for TLS streams, while `connectOptimistic()` is still connecting or after the circuit has closed.
+   *
+   * This is informational only and must not be used for security decisions.
+   */
+  #[napi]
+  pub fn get_circuit_fingerprints(&self) -> napi::Result<Vec<String>> {
+    let data_stream = match self.stream.as_ref().map(|stream| &stream.inner) {
+      Some(MaybeTlsStream::Plain(stream)) => stream,
+      Some(MaybeTlsStream::Tls(_) | MaybeTlsStream::Connecting(_)) => return Ok(Vec::new()),
+      None => return Err(napi::Error::from_reason("Stream was closed")),
+    };
+
+    let Some(path) = data_stream
+      .client_stream_ctrl()
+      .and_then(|ctrl| ctrl.circuit())
+      .and_then(|circuit| circuit.path_ref().ok())
+    else {
+      return Ok(Vec::new());
+    };
+
+    Ok(
+      path
+        .iter()
+        .filter_map(|hop| hop.as_chan_target()?.rsa_identity().copied())
+        .map(|id| id.as_bytes().iter().map(|b| format!("{b:02X}")).collect())
+        .collect(),
+    )
+  }
+
+  /**
+   * Moves the stream into a Node.js `Duplex`, so it can be used with `pipe()`, `stream.pipeline()` or
+   * any library expecting a socket-like object. Afterwards this `TorStream` behaves as if it was closed.
+   * Calling `close()` before the move, or destroying the duplex, ends the underlying Tor stream and
+   * emits `close` on the duplex.
+   *
+   * Requires `process.getBuiltinModule`, available since Node.js 20.16.
+   *
+   * @throws If the stream is closed.
+   */
+  #[napi(ts_return_type = "import('stream').Duplex")]
+  #[allow(clippy::wrong_self_convention)]
+  pub fn into_node_duplex(&mut self, env: Env) -> napi::Result<JsObject> {
+    let duplex_class = duplex_constructor(&env)?;
+    let stream = self
+      .stream
+      .take()
+      .ok_or(napi::Error::from_reason("Stream was closed"))?;
+    let token = std::mem::take(&mut self.cancel_token);
+    let counters = std::mem::take(&mut self.counters);
+    let guard = Arc::new(DuplexGuard::new(token.clone(), counters.service.clone()));
+    let io = Arc::new(DuplexIo::new(stream, counters));
+
+    let read = {
+      let io = io.clone();
+      let token = token.clone();
+      env.create_function_from_closure("read", move |ctx| {
+        let size = ctx.get::<JsUnknown>(0)?.coerce_to_number()?.get_double()?;
+        let len = (size as usize).clamp(1, 64 * 1024);
+        let this = ctx.this::<JsObject>()?;
+        let push = chunk_callback(bound_method(&this, "push")?)?;
+        let destroy = error_callback(bound_method(&this, "destroy")?)?;
+
+        let io = io.clone();
+        let token = token.clone();
+        within_runtime_if_available(|| {
+          tokio::spawn(async move {
+            let result = tokio::select! {
+              biased;
+
+              _ = token.cancelled() => return,
+              result = io.read(len) => result,
+            };
+            match result {
+              Ok(chunk) => push.call(chunk, ThreadsafeFunctionCallMode::NonBlocking),
+              Err(err) => destroy.call(
+                Some(err.to_string()),
+                ThreadsafeFunctionCallMode::NonBlocking,
+              ),
+            };
+          })
+        });
+        ctx.env.get_undefined()
+      })?
+    };
+
+    let write = {
+      let io = io.clone();
+      let token = token.clone();
+      env.create_function_from_closure("write", move |ctx| {
+        let chunk = ctx.get::<JsBuffer>(0)?.into_value()?.to_vec();
+        let callback = error_callback(ctx.get::<JsFunction>(2)?)?;
+
+        let io = io.clone();
+        let token = token.clone();
+        within_runtime_if_available(|| {
+          tokio::spawn(async move {
+            let result = tokio::select! {
+              biased;
+
+              _ = token.cancelled() => Err("Stream was closed during write".to_string()),
+              result = io.write(&chunk) => result.map_err(|err| err.to_string()),
+            };
+            callback.call(result.err(), ThreadsafeFunctionCallMode::NonBlocking);
+          })
+        });
+        ctx.env.get_undefined()
+      })?
+    };
+
+    let final_ = {
+      let io = io.clone();
+      env.create_function_from_closure("final", move |ctx| {
+        let callback = error_callback(ctx.get::<JsFunction>(0)?)?;
+
+        let io = io.clone();
+        within_runtime_if_available(|| {
+          tokio::spawn(async move {
+            let result = io.shutdown().await.map_err(|err| err.to_string());
+            callback.call(result.err(), ThreadsafeFunctionCallMode::NonBlocking);
+          })
+        });
+        ctx.env.get_undefined()
+      })?
+    };
+
+    let destroy = {
+      let guard = guard.clone();
+      env.create_function_from_closure("destroy", move |ctx| {
+        guard.close();
+        let err = ctx.get::<JsUnknown>(0)?;
+        ctx.get::<JsFunction>(1)?.call(None, &[err])?;
+        ctx.env.get_undefined()
+      })?
+    };
+
+    let mut options = env.create_object()?;
+    options.set_named_property("read", read)?;
+    options.set_named_property("write", write)?;
+    options.set_named_property("final", final_)?;
+    options.set_named_property("destroy", destroy)?;
+    let mut duplex = duplex_class.new_instance(&[options])?;
+    duplex.add_finalizer(guard, (), |ctx| ctx.value.close())?;
+
+    // Closing the stream from Rust destroys the duplex, which emits `close`.
+    // The duplex is only held weakly, so this task does not keep it from being garbage-collected.
+    let mut on_cancel = error_callback(weak_method(&env, &duplex, "destroy")?)?;
+    on_cancel.unref(&env)?;
+    within_runtime_if_available(|| {
+      tokio::spawn(async move {
+        token.cancelled().await;
+        on_cancel.call(None, ThreadsafeFunctionCallMode::NonBlocking);
+      })
+    });
+
+    Ok(duplex)
+  }
+
+  /**
+   * Close the stream.
+   */
+  #[napi]
+  pub unsafe fn close(&mut self) {
+    self.stream.take();
+    self.cancel_token.cancel();
+    if let Some(counters) = self.counters.service.take() {
+      counters.connection_closed();
+    }
+  }
+}
+
+impl NativeTorStream {
+  async fn read_array<const N: usize>(&mut self) -> napi::Result<[u8; N]> {
+    let token = self.cancel_token.clone();
+
+    let read_fut = async {
+      let Some(stream) = &mut self.stream else {
+        return Err(napi::Error::from_reason("Stream was closed"));
+      };
+
+      let mut buf = [0u8; N];
+      utils::map_error(stream.read_exact(&mut buf).await)?;
+      record_received(&self.counters, N);
+      Ok(buf)
+    };
+
+    tokio::select! {
+      biased;
+
+      _ = token.cancelled() => {
+        Err(napi::Error::from_reason("Stream was closed during read"))
+      }
+
+      result = read_fut => result
+    }
+  }
+
+  async fn write_bytes(&mut self, buf: &[u8]) -> napi::Result<()> {
+    if let Some(stream) = &mut self.stream {
+      utils::map_error(stream.write_all(buf).await)?;
+      record_sent(&self.counters, buf.len());
+      if self.nodelay {
+        utils::map_error(stream.flush().await)?;
+      }
+      Ok(())
+    } else {
+      Err(napi::Error::from_reason("Stream was closed"))
+    }
+  }
+}
+
+fn unix_time_ms() -> f64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
+
+fn bigint_to_u64(value: BigInt) -> napi::Result<u64> {
+  let (signed, value, lossless) = value.get_u64();
+  if signed || !lossless {
+    return Err(napi::Error::from_reason(
+      "Value must be an unsigned 64-bit integer",
+    ));
+  }
+  Ok(value)
+}
+
+fn record_received(counters: &StreamCounters, bytes: usize) {
+  if let Some(service) = &counters.service {
+    service.bytes_received(bytes);
+  }
+  if let Some(client) = &counters.client {
+    client.bytes_received(bytes);
+  }
+}
+
+fn record_sent(counters: &StreamCounters, bytes: usize) {
+  if let Some(service) = &counters.service {
+    service.bytes_sent(bytes);
+  }
+  if let Some(client) = &counters.client {
+    client.bytes_sent(bytes);
+  }
+}
+
+/// Returns the `Duplex` class from Node's `stream` module.
+fn duplex_constructor(env: &Env) -> napi::Result<JsFunction> {
+  let process = env
+    .get_global()?
+    .get_named_property::<JsObject>("process")?;
+  let get_builtin_module = process.get_named_property::<JsUnknown>("getBuiltinModule")?;
+  if get_builtin_module.get_type()? != ValueType::Function {
+    return Err(napi::Error::from_reason(
+      "Duplex streams require Node.js 20.16 or newer",
+    ));
+  }
+
+  let get_builtin_module: JsFunction = unsafe { get_builtin_module.cast() };
+  get_builtin_module
+    .call(Some(&process), &[env.create_string("stream")?])?
+    .coerce_to_object()?
+    .get_named_property::<JsFunction>("Duplex")
+}
+
+/// Returns `this[name]` bound to `this`, so it can be called from a threadsafe function.
+fn bound_method(this: &JsObject, name: &str) -> napi::Result<JsFunction> {
+  let method = this
+    .get_named_property::<JsFunction>(name)?
+    .coerce_to_object()?;
+  let bind = method.get_named_property::<JsFunction>("bind")?;
+  let bound = bind.call(Some(&method), &[this])?;
+  Ok(unsafe { bound.cast() })
+}
+
+/// Returns a function that calls `this[name](...args)` while `this` is alive, without keeping it alive.
+fn weak_method(env: &Env, this: &JsObject, name: &'static str) -> napi::Result<JsFunction> {
+  let weak = env
+    .get_global()?
+    .get_named_property::<JsFunction>("WeakRef")?
+    .new_instance(&[this])?;
+  let method = env.create_function_from_closure(name, move |ctx| {
+    let weak = ctx.this::<JsObject>()?;
+    let target = weak
+      .get_named_property::<JsFunction>("deref")?
+      .call_without_args(Some(&weak))?;
+    if target.get_type()? == ValueType::Object {
+      let target: JsObject = unsafe { target.cast() };
+      let args = (0..ctx.length)
+        .map(|index| ctx.get::<JsUnknown>(index))
+        .collect::<napi::Result<Vec<_>>>()?;
+      target
+        .get_named_property::<JsFunction>(name)?
+        .call(Some(&target), &args)?;
+    }
+    ctx.env.get_undefined()
+  })?;
+  let method = method.coerce_to_object()?;
+  let bind = method.get_named_property::<JsFunction>("bind")?;
+  let bound = bind.call(Some(&method), &[weak])?;
+  Ok(unsafe { bound.cast() })
+}
+
+/// Wraps a `push(chunk)` function; an empty chunk is pushed as `null` to signal EOF.
+fn chunk_callback(
+  callback: JsFunction,
+) -> napi::Result<ThreadsafeFunction<Vec<u8>, ErrorStrategy::Fatal>> {
+  callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<u8>>| {
+    let arg = if ctx.value.is_empty() {
+      ctx.env.get_null()?.into_unknown()
+    } else {
+      ctx
+        .env
+        .create_buffer_with_data(ctx.value)?
+        .into_raw()
+        .into_unknown()
+    };
+    Ok(vec![arg])
+  })
+}
+
+/// Wraps a Node-style `callback(err?)`; `None` calls it without arguments.
+fn error_callback(
+  callback: JsFunction,
+) -> napi::Result<ThreadsafeFunction<Option<String>, ErrorStrategy::Fatal>> {
+  callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Option<String>>| {
+    let arg: Option<JsUnknown> = match ctx.value {
+      Some(message) => Some(
+        ctx
+          .env
+          .create_error(napi::Error::from_reason(message))?
+          .into_unknown(),
+      ),
+      None => None,
+    };
+    Ok(arg.into_iter().collect::<Vec<_>>())
+  })
 }
 
 impl ObjectFinalize for NativeTorStream {
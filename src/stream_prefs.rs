@@ -1,11 +1,38 @@
 use crate::utils;
+use arti_client::isolation::IsolationHelper;
 use arti_client::{CountryCode, StreamPrefs};
 use std::str::FromStr;
 
+/// Isolation group identified by arbitrary caller-provided bytes.
+/// Streams using equal keys may share circuits; streams using different keys never do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IsolationKey(Vec<u8>);
+
+impl IsolationKey {
+  pub fn new(key: impl Into<Vec<u8>>) -> Self {
+    Self(key.into())
+  }
+}
+
+impl IsolationHelper for IsolationKey {
+  fn compatible_same_type(&self, other: &Self) -> bool {
+    self == other
+  }
+
+  fn join_same_type(&self, other: &Self) -> Option<Self> {
+    self.compatible_same_type(other).then(|| self.clone())
+  }
+}
+
 #[napi(js_name = "StreamPrefs")]
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct NativeStreamPrefs {
   prefs: StreamPrefs,
+  // arti does not expose these preferences once set, so they are tracked here.
+  exit_country: Option<String>,
+  ipv4_only: bool,
+  ipv6_only: bool,
+  isolated: bool,
 }
 
 #[napi]
@@ -26,6 +53,7 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn any_exit_country(&mut self) -> &Self {
     self.prefs.any_exit_country();
+    self.exit_country = None;
     self
   }
 
@@ -37,9 +65,9 @@ impl NativeStreamPrefs {
    */
   #[napi]
   pub fn exit_country(&mut self, country_code: String) -> napi::Result<&Self> {
-    self
-      .prefs
-      .exit_country(utils::map_error(CountryCode::from_str(&country_code))?);
+    let country_code = utils::map_error(CountryCode::from_str(&country_code))?;
+    self.exit_country = Some(country_code.to_string());
+    self.prefs.exit_country(country_code);
     Ok(self)
   }
 
@@ -61,6 +89,8 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn ipv4_only(&mut self) -> &Self {
     self.prefs.ipv4_only();
+    self.ipv4_only = true;
+    self.ipv6_only = false;
     self
   }
 
@@ -71,6 +101,8 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn ipv6_only(&mut self) -> &Self {
     self.prefs.ipv6_only();
+    self.ipv4_only = false;
+    self.ipv6_only = true;
     self
   }
 
@@ -81,6 +113,8 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn ipv4_preferred(&mut self) -> &Self {
     self.prefs.ipv4_preferred();
+    self.ipv4_only = false;
+    self.ipv6_only = false;
     self
   }
 
@@ -90,6 +124,8 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn ipv6_preferred(&mut self) -> &Self {
     self.prefs.ipv6_preferred();
+    self.ipv4_only = false;
+    self.ipv6_only = false;
     self
   }
 
@@ -109,6 +145,7 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn isolate_every_stream(&mut self) -> &Self {
     self.prefs.isolate_every_stream();
+    self.isolated = true;
     self
   }
 
@@ -118,6 +155,7 @@ impl NativeStreamPrefs {
   #[napi]
   pub fn new_isolation_group(&mut self) -> &Self {
     self.prefs.new_isolation_group();
+    self.isolated = true;
     self
   }
 
@@ -132,6 +170,65 @@ impl NativeStreamPrefs {
     self
   }
 
+  /**
+   * Replaces every preference with those of `other`, so a base configuration can be copied and customised.
+   */
+  #[napi]
+  pub fn copy_from(&mut self, other: &NativeStreamPrefs) -> &Self {
+    if !std::ptr::eq(self, other) {
+      *self = other.clone();
+    }
+    self
+  }
+
+  /**
+   * Returns a new `StreamPrefs` with the same preferences, which can be modified without affecting this one.
+   */
+  #[napi]
+  pub fn clone_prefs(&self) -> NativeStreamPrefs {
+    self.clone()
+  }
+
+  /**
+   * Returns the country exit relays must be located in, or `null|undefined` if any country is allowed.
+   */
+  #[napi]
+  pub fn get_exit_country(&self) -> Option<String> {
+    self.exit_country.clone()
+  }
+
+  /**
+   * Returns true if streams may only be made over IPv4.
+   */
+  #[napi]
+  pub fn get_is_ipv4_only(&self) -> bool {
+    self.ipv4_only
+  }
+
+  /**
+   * Returns true if streams may only be made over IPv6.
+   */
+  #[napi]
+  pub fn get_is_ipv6_only(&self) -> bool {
+    self.ipv6_only
+  }
+
+  /**
+   * Returns true if `isolateEveryStream()` or `newIsolationGroup()` has been called.
+   */
+  #[napi]
+  pub fn get_is_isolated(&self) -> bool {
+    self.isolated
+  }
+
+  /**
+   * Returns true if streams are opened "optimistically".
+   */
+  #[napi]
+  pub fn get_is_optimistic(&self) -> bool {
+    self.prefs.is_optimistic()
+  }
+
   pub fn get(&self) -> StreamPrefs {
     self.prefs.clone()
   }